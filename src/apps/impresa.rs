@@ -1,6 +1,7 @@
 extern crate kernel;
 
 use std::thread;
+use std::sync::mpsc;
 use kernel::reactors::scheduler::Scheduler;
 use kernel::sys;
 use kernel::args;
@@ -24,14 +25,26 @@ pub fn star<'a>(sched_num: usize) -> Vec<Scheduler<'a>> {
 
 pub fn park<'a>(mut scheds: Vec<Scheduler<'a>>) -> Scheduler<'a> {
     let sz = scheds.len();
+    let mut threads = vec![];
+    let (stopped, acks) = mpsc::channel();
+    sys::block_signals();
     for id in 1..sz {
         if let Some(mut core) = scheds.pop() {
+            let core_id = core.bus.id;
+            let stopped = stopped.clone();
             unsafe {
-                spawn_on(id, move || core.run());
+                threads.push((core_id,
+                              spawn_on(id, move || {
+                    core.run();
+                    stopped.send(core_id).ok();
+                })));
             }
         }
     }
-    scheds.pop().expect("No BSP")
+    let mut bsp = scheds.pop().expect("No BSP");
+    bsp.threads = threads;
+    bsp.stopped = Some(acks);
+    bsp
 }
 
 trait FnBox {
@@ -55,7 +68,7 @@ pub unsafe fn spawn_on<'a, F>(id: usize, f: F) -> thread::JoinHandle<()>
     thread::Builder::new()
         .name(format!("core_{}", id))
         .spawn(move || {
-            sys::set_affinity(1 << id);
+            sys::set_affinity(id); // a cpu index, not a mask
             closure.call_box()
         })
         .expect("Can't spawn new thread!")
@@ -18,7 +18,7 @@ impl<'a> Parser {
         Parser { args: env::args().collect() }
     }
 
-    pub fn get(&mut self, arg: &str, hasval: bool) -> Result<Option<&String>, Error> {
+    pub fn get(&self, arg: &str, hasval: bool) -> Result<Option<&String>, Error> {
         let mut it = self.args.iter();
         // omit an 0th argument (program name)
         let v = it.next();
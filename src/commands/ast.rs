@@ -2,11 +2,13 @@
 // O-DSL AST
 
 use std::fmt;
+use std::cmp;
 use std::iter;
 use std::result::Result;
 use std::collections::HashMap;
 use commands::command;
 use streams::otree;
+use streams::verb::num;
 use reactors::task::Context;
 use streams::interpreter::*;
 use std::cell::UnsafeCell;
@@ -16,6 +18,11 @@ use std::rc::Rc;
 use core::ops::Deref;
 use core::slice::Iter;
 use std::mem;
+use std::{i64, f64};
+
+// k nulls: 0N for integers and 0n for floats
+pub const NULL_INT: i64 = i64::MIN;
+pub const NULL_FLOAT: f64 = f64::NAN;
 
 #[derive(Debug)]
 pub enum Error {
@@ -230,13 +237,93 @@ pub enum Value {
     Nil,
     SymbolInt(u16),
     SequenceInt(u16),
-    Number(i64),
+    Bool(bool),
+    Byte(u8),
+    Short(i16),
+    Int32(i32),
+    Number(i64), // long
+    Real(f32),
     Float(f64),
+    VecBool(Vec<bool>),
+    VecByte(Vec<u8>),
+    VecShort(Vec<i16>),
+    VecInt32(Vec<i32>),
     VecInt(Vec<i64>),
+    VecReal(Vec<f32>),
     VecFloat(Vec<f64>),
+    List(Vec<Value>), // general (mixed) list
     Ioverb(String),
 }
 
+macro_rules! pack_vec {
+    ($v:expr, $atom:ident, $vec:ident) => {
+        Value::$vec($v.into_iter()
+            .map(|x| match x {
+                Value::$atom(x) => x,
+                _ => panic!("Unexpected non-number"),
+            })
+            .collect())
+    }
+}
+
+impl Default for Value {
+    fn default() -> Value {
+        Value::Nil
+    }
+}
+
+impl Value {
+    pub fn is_vector(&self) -> bool {
+        match *self {
+            Value::VecBool(_) |
+            Value::VecByte(_) |
+            Value::VecShort(_) |
+            Value::VecInt32(_) |
+            Value::VecInt(_) |
+            Value::VecReal(_) |
+            Value::VecFloat(_) |
+            Value::List(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn items(&self) -> Option<Vec<Value>> {
+        // explodes any vector into its atoms
+        match *self {
+            Value::VecBool(ref v) => Some(v.iter().map(|&x| Value::Bool(x)).collect()),
+            Value::VecByte(ref v) => Some(v.iter().map(|&x| Value::Byte(x)).collect()),
+            Value::VecShort(ref v) => Some(v.iter().map(|&x| Value::Short(x)).collect()),
+            Value::VecInt32(ref v) => Some(v.iter().map(|&x| Value::Int32(x)).collect()),
+            Value::VecInt(ref v) => Some(v.iter().map(|&x| Value::Number(x)).collect()),
+            Value::VecReal(ref v) => Some(v.iter().map(|&x| Value::Real(x)).collect()),
+            Value::VecFloat(ref v) => Some(v.iter().map(|&x| Value::Float(x)).collect()),
+            Value::List(ref v) => Some(v.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn pack(v: Vec<Value>) -> Value {
+        // same typed numbers collapse into specialized vector, anything else is a general list
+        let r = if v.len() == 0 { num::RANK_NONE } else { atom_rank(&v[0]) };
+        if r == num::RANK_NONE || !v.iter().all(|x| atom_rank(x) == r) {
+            return Value::List(v);
+        }
+        match r {
+            num::RANK_BOOL => pack_vec!(v, Bool, VecBool),
+            num::RANK_BYTE => pack_vec!(v, Byte, VecByte),
+            num::RANK_SHORT => pack_vec!(v, Short, VecShort),
+            num::RANK_INT => pack_vec!(v, Int32, VecInt32),
+            num::RANK_REAL => pack_vec!(v, Real, VecReal),
+            num::RANK_FLOAT => pack_vec!(v, Float, VecFloat),
+            _ => pack_vec!(v, Number, VecInt),
+        }
+    }
+}
+
+fn atom_rank(v: &Value) -> u8 {
+    if v.is_vector() { num::RANK_NONE } else { num::rank(v) }
+}
+
 #[derive(PartialEq,Debug,Clone)]
 pub enum AST<'a> {
     Atom(Atom<'a>),
@@ -261,62 +348,70 @@ fn delta<'a>(this: &Cont<'a>, next: &Cont<'a>) -> usize {
     unsafe { (this_ptr - next_ptr) / size_of::<Cont<'a>>() }
 }
 
-fn is_int(x: &AST) -> bool {
+fn scalar_rank(x: &AST) -> u8 {
     match x {
-        &AST::Atom(Atom::Value(Value::Number(_))) => true,
-        _ => false,
+        &AST::Atom(Atom::Value(ref v)) => atom_rank(v),
+        _ => num::RANK_NONE,
     }
 }
 
-fn is_float(x: &AST) -> bool {
-    match x {
-        &AST::Atom(Atom::Value(Value::Float(_))) => true,
-        _ => false,
-    }
-}
-
-fn fn_false(x: &AST) -> bool {
-    false
-}
-
-fn is_monovec<'a>(n: &'a Vec<AST<'a>>) -> bool {
+fn is_monovec<'a>(n: &Vec<AST<'a>>) -> bool {
     if n.len() == 0 {
         false
     } else {
-        // yes, Rust does not like closures in match :(
-        let pred = match n[0] {
-            AST::Atom(Atom::Value(Value::Number(_))) => is_int,
-            AST::Atom(Atom::Value(Value::Float(_))) => is_float,
-            _ => fn_false,
-        };
-
-        let isvec = n.iter().all(pred);
-        isvec
+        let r = scalar_rank(&n[0]);
+        r != num::RANK_NONE && n.iter().all(|x| scalar_rank(x) == r)
     }
 }
 
-fn to_monovec<'a, 'b>(n: &'a Vec<AST<'a>>) -> Atom<'b> {
-    // converts list of integers/floats to specialized vector
+macro_rules! collect_vec {
+    ($n:expr, $atom:ident, $vec:ident) => {
+        Atom::Value(Value::$vec($n.iter()
+            .map(|v| match v {
+                &AST::Atom(Atom::Value(Value::$atom(x))) => x,
+                _ => panic!("Unexpected non-number"),
+            })
+            .collect()))
+    }
+}
 
-    let mut i: Vec<i64> = vec![];
-    let mut f: Vec<f64> = vec![];
+fn to_monovec<'a, 'b>(n: &Vec<AST<'a>>) -> Atom<'b> {
+    // converts list of same typed numbers to specialized vector
+
+    match n[0] {
+        AST::Atom(Atom::Value(Value::Bool(_))) => collect_vec!(n, Bool, VecBool),
+        AST::Atom(Atom::Value(Value::Byte(_))) => collect_vec!(n, Byte, VecByte),
+        AST::Atom(Atom::Value(Value::Short(_))) => collect_vec!(n, Short, VecShort),
+        AST::Atom(Atom::Value(Value::Int32(_))) => collect_vec!(n, Int32, VecInt32),
+        AST::Atom(Atom::Value(Value::Real(_))) => collect_vec!(n, Real, VecReal),
+        AST::Atom(Atom::Value(Value::Float(_))) => collect_vec!(n, Float, VecFloat),
+        _ => collect_vec!(n, Number, VecInt),
+    }
+}
 
-    for v in n.iter() {
-        match v {
-            &AST::Atom(Atom::Value(Value::Number(x))) => {
-                i.push(x);
-            }
-            &AST::Atom(Atom::Value(Value::Float(x))) => {
-                f.push(x);
-            }
-            _ => panic!("Unexpected non-number"),
+fn strand<'a>(n: &AST<'a>, acc: &mut Vec<AST<'a>>) -> Option<Option<(Verb, &'a AST<'a>)>> {
+    // `1 2 3` is parsed as a chain of calls on number literals,
+    // in `1 2 3+x` the chain ends with a verb applied to the last literal
+    match n {
+        &AST::Atom(Atom::Call(a, b)) if scalar_rank(a) != num::RANK_NONE => {
+            acc.push(a.clone());
+            strand(b, acc)
         }
+        &AST::Atom(Atom::Verb(v, l, r)) if scalar_rank(l) != num::RANK_NONE => {
+            acc.push(l.clone());
+            Some(Some((v, r)))
+        }
+        x if scalar_rank(x) != num::RANK_NONE => {
+            acc.push(x.clone());
+            Some(None)
+        }
+        _ => None,
     }
-    if i.len() >= f.len() {
-        Atom::Value(Value::VecInt(i))
-    } else {
-        Atom::Value(Value::VecFloat(f))
-    }
+}
+
+fn is_strand<'a>(n: &AST<'a>) -> bool {
+    let mut s = Vec::new();
+    strand(n, &mut s).is_some() && is_monovec(&s)
 }
 
 pub fn postprocess_ast<'a, 'b>(n: &'b AST<'a>, skip_depth: i64, arena: &'a Arena<'a>) -> &'a AST<'a> {
@@ -327,7 +422,8 @@ pub fn postprocess<'a, 'b>(n: &'b AST<'a>, skip_depth: i64, arena: &'a Arena<'a>
     // AST postprocessing
     // - lists of integers => VecInt
     // - lists of floats   => VecFloat
-    // - general lists     => VecAST
+    // - strands `1 2 3`   => VecInt
+    // - general lists     => Value::List after evaluation
 
     // println!("postprocess input: {:?}", n);
 
@@ -340,6 +436,17 @@ pub fn postprocess<'a, 'b>(n: &'b AST<'a>, skip_depth: i64, arena: &'a Arena<'a>
                     Atom::Assign(postprocess_ast(a, skip_depth, arena),
                                 postprocess_ast(b, skip_depth, arena))
                 } 
+                &Atom::Call(_, _) if is_strand(n) => {
+                    let mut s = Vec::new();
+                    match strand(n, &mut s) {
+                        Some(Some((v, r))) => {
+                            Atom::Verb(v,
+                                       arena.ast(AST::Atom(to_monovec(&s))),
+                                       postprocess_ast(r, skip_depth, arena))
+                        }
+                        _ => to_monovec(&s),
+                    }
+                }
                 &Atom::Call(a, b) => {
                     Atom::Call(postprocess_ast(a, skip_depth, arena),
                               postprocess_ast(b, 3 /* Call->Dict->VecAST nodes */, arena))
@@ -354,6 +461,9 @@ pub fn postprocess<'a, 'b>(n: &'b AST<'a>, skip_depth: i64, arena: &'a Arena<'a>
                                 postprocess_ast(a, skip_depth, arena),
                                 postprocess_ast(b, 2 /* Lambda->VecAST nodes */, arena))
                 }
+                &Atom::Verb(v, a @ &AST::Atom(Atom::Value(Value::Nil)), b @ &AST::Atom(Atom::Dict(_))) => {
+                    Atom::Verb(v, a, postprocess_ast(b, 3 /* Verb->Dict->VecAST nodes */, arena))
+                }
                 &Atom::Verb(v, a, b) => {
                     Atom::Verb(v,
                               postprocess_ast(a, skip_depth, arena),
@@ -383,11 +493,16 @@ pub fn postprocess<'a, 'b>(n: &'b AST<'a>, skip_depth: i64, arena: &'a Arena<'a>
 }
 
 pub fn parse<'a>(arena: &'a Arena<'a>, s: &String) -> &'a AST<'a> {
-    let ast = command::parse_Mex(arena, s).unwrap();
+    try_parse(arena, s).unwrap()
+}
+
+pub fn try_parse<'a>(arena: &'a Arena<'a>, s: &str) -> Result<&'a AST<'a>, Error> {
+    // for source that comes from elsewhere, bad input is an error and not a panic
+    let ast = try!(command::parse_Mex(arena, s).map_err(|_| Error::ParseError));
     // println!("parse {:?}", ast);
     let p_ast = postprocess(&ast, 0, arena);
     // println!("post parse {:?}", p_ast);
-    arena.ast(p_ast)
+    Ok(arena.ast(p_ast))
 }
 
 struct viter<'a>(&'a ASTIter<'a>);
@@ -410,7 +525,7 @@ impl<'a> fmt::Display for Cont<'a> {
                        delta(self, cont))
             }
             &Cont::Assign(name, cont) => write!(f, "assign: {} next: {}", name, delta(self, cont)),
-            &Cont::List(_, _, cont) => write!(f, "list: NYI next: {}", delta(self, cont)),
+            &Cont::List(cont) => write!(f, "list: next: {}", delta(self, cont)),
             &Cont::Dict(ref acc, ref rest, cont) => {
                 write!(f,
                        "dict: {} {} next: {}",
@@ -418,14 +533,8 @@ impl<'a> fmt::Display for Cont<'a> {
                        viter(rest),
                        delta(self, cont))
             }
-            &Cont::DictComplete(ref acc, ref rest, idx, cont) => {
-                write!(f,
-                       "dict_complete: {} {} next: {}",
-                       acc,
-                       viter(rest),
-                       delta(self, cont))
-            }
             &Cont::Adverb(adv, _, cont) => write!(f, "adverb: {} next: {}", adv, delta(self, cont)),
+            &Cont::Apply(args, cont) => write!(f, "apply: {} next: {}", args, delta(self, cont)),
             &Cont::Verb(ref verb, right, swap, cont) => {
                 write!(f, "verb: {} {} next: {}", verb, right, delta(self, cont))
             }
@@ -523,6 +632,11 @@ impl<'a> Arena<'a> {
         })
     }
 
+    pub fn name_id(&self, s: &str) -> Option<u16> {
+        let names = unsafe { &*self.names.get() };
+        names.get(s).cloned()
+    }
+
     pub fn intern_ast(&self, s: String) -> &'a AST<'a> {
         self.ast(self.intern(s))
     }
@@ -539,6 +653,21 @@ impl<'a> Arena<'a> {
         })
     }
 
+    pub fn name(&self, id: u16) -> Option<String> {
+        let names = unsafe { &*self.names.get() };
+        names.iter().find(|&(_, &v)| v == id).map(|(k, _)| k.clone())
+    }
+
+    pub fn symbol_name(&self, id: u16) -> Option<String> {
+        let symbols = unsafe { &*self.symbols.get() };
+        symbols.iter().find(|&(_, &v)| v == id).map(|(k, _)| k.clone())
+    }
+
+    pub fn sequence_name(&self, id: u16) -> Option<String> {
+        let sequences = unsafe { &*self.sequences.get() };
+        sequences.iter().find(|&(_, &v)| v == id).map(|(k, _)| k.clone())
+    }
+
     pub fn intern_symbol_ast(&self, s: String) -> &'a AST<'a> {
         self.ast(self.intern_symbol(s))
     }
@@ -627,6 +756,14 @@ impl<'a> iter::IntoIterator for &'a AST<'a> {
     }
 }
 
+fn fmt_int(x: i64) -> String {
+    if x == NULL_INT { "0N".to_string() } else { x.to_string() }
+}
+
+fn fmt_float(x: f64) -> String {
+    if x.is_nan() { "0n".to_string() } else { x.to_string() }
+}
+
 struct vi64<'a>(&'a Vec<i64>);
 
 impl<'a> fmt::Display for vi64<'a> {
@@ -634,7 +771,7 @@ impl<'a> fmt::Display for vi64<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let str = self.0
             .into_iter()
-            .map(|x| x.to_string())
+            .map(|&x| fmt_int(x))
             .fold(String::new(), |acc, x| if acc == "" {
                 x
             } else {
@@ -651,7 +788,7 @@ impl<'a> fmt::Display for vf64<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let str = self.0
             .into_iter()
-            .map(|x| x.to_string())
+            .map(|&x| fmt_float(x))
             .fold(String::new(), |acc, x| if acc == "" {
                 x
             } else {
@@ -677,6 +814,48 @@ impl<'a> fmt::Display for vast<'a> {
     }
 }
 
+struct vval<'a>(&'a Vec<Value>);
+
+impl<'a> fmt::Display for vval<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = self.0
+            .into_iter()
+            .map(|x| x.to_string())
+            .fold(String::new(), |acc, x| if acc == "" {
+                x
+            } else {
+                format!("{};{}", acc, x)
+            });
+        write!(f, "{}", str)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Value::Nil => write!(f, "Nil"),
+            &Value::Number(n) => write!(f, "{}", fmt_int(n)),
+            &Value::Float(n) => write!(f, "{}", fmt_float(n)),
+            &Value::Bool(b) => write!(f, "{}b", b as u8),
+            &Value::Byte(b) => write!(f, "0x{:02x}", b),
+            &Value::Short(n) => write!(f, "{}h", fmt_int(num::conv::<i16, i64>(n))),
+            &Value::Int32(n) => write!(f, "{}i", fmt_int(num::conv::<i32, i64>(n))),
+            &Value::Real(n) => write!(f, "{}e", fmt_float(n as f64)),
+            &Value::VecBool(ref v) => write!(f, "{}b", v.iter().map(|&b| if b { "1" } else { "0" }).collect::<String>()),
+            &Value::VecByte(ref v) => write!(f, "0x{}", v.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+            &Value::VecShort(ref v) => write!(f, "#h[{}]", vi64(&v.iter().map(|&x| num::conv(x)).collect())),
+            &Value::VecInt32(ref v) => write!(f, "#i32[{}]", vi64(&v.iter().map(|&x| num::conv(x)).collect())),
+            &Value::VecReal(ref v) => write!(f, "#e[{}]", vf64(&v.iter().map(|&x| x as f64).collect())),
+            &Value::SymbolInt(ref s) => write!(f, "{}", s),
+            &Value::SequenceInt(ref s) => write!(f, "{:?}s", s),
+            &Value::VecInt(ref v) => write!(f, "#i[{}]", vi64(v)),
+            &Value::VecFloat(ref v) => write!(f, "#f[{}]", vf64(v)),
+            &Value::List(ref v) => write!(f, "#a[{}]", vval(v)),
+            &Value::Ioverb(ref v) => write!(f, "{}", v),
+        }
+    }
+}
+
 impl<'a> fmt::Display for AST<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -700,18 +879,7 @@ impl<'a> fmt::Display for AST<'a> {
             AST::Atom(Atom::Cond(ref c, ref a, ref b)) => write!(f, "$[{};{};{}]", c, a, b),
             AST::Atom(Atom::Yield(ref c)) => write!(f, "Yield {:?}", c),
             AST::Atom(Atom::NameInt(ref n)) => write!(f, "^{}", n),
-            AST::Atom(Atom::Value(ref v)) => {
-                match v {
-                    &Value::Nil => write!(f, "Nil"),
-                    &Value::Number(n) => write!(f, "{}", n),
-                    &Value::SymbolInt(ref s) => write!(f, "{}", s),
-                    &Value::SequenceInt(ref s) => write!(f, "{:?}s", s),
-                    &Value::VecInt(ref v) => write!(f, "#i[{}]", vi64(v)),
-                    &Value::VecFloat(ref v) => write!(f, "#f[{}]", vf64(v)),
-                    &Value::Ioverb(ref v) => write!(f, "{}", v),
-                    _ => write!(f, "Not implemented yet."),
-                }
-            }
+            AST::Atom(Atom::Value(ref v)) => write!(f, "{}", v),
             AST::Vector(ref v) => write!(f, "#a[{}]", vast(v)),
             _ => write!(f, "Not implemented yet."),
         }
@@ -729,13 +897,53 @@ pub fn call<'a>(l: &'a AST<'a>, r: &'a AST<'a>, arena: &'a Arena<'a>) -> AST<'a>
     AST::Atom(Atom::Call(l, r))
 }
 
+fn valence(n: &AST, ids: &[Option<u16>]) -> usize {
+    // highest of implicit x, y, z used in body, nested lambdas have their own
+    match n {
+        &AST::Vector(ref v) => v.iter().map(|x| valence(x, ids)).max().unwrap_or(0),
+        &AST::Atom(Atom::NameInt(s)) => ids.iter().position(|&i| i == Some(s)).map(|p| p + 1).unwrap_or(0),
+        &AST::Atom(Atom::List(a)) |
+        &AST::Atom(Atom::Dict(a)) => valence(a, ids),
+        &AST::Atom(Atom::Call(a, b)) |
+        &AST::Atom(Atom::Assign(a, b)) |
+        &AST::Atom(Atom::Table(a, b)) |
+        &AST::Atom(Atom::Verb(_, a, b)) |
+        &AST::Atom(Atom::Adverb(_, a, b)) => cmp::max(valence(a, ids), valence(b, ids)),
+        &AST::Atom(Atom::Cond(a, b, c)) => cmp::max(valence(a, ids), cmp::max(valence(b, ids), valence(c, ids))),
+        _ => 0,
+    }
+}
+
 pub fn fun<'a>(l: &'a AST<'a>, r: &'a AST<'a>, arena: &'a Arena<'a>) -> AST<'a> {
     AST::Atom(match *l {
-        AST::Atom(Atom::Value(Value::Nil)) => Atom::Lambda(None, arena.intern_ast("x".to_string()), r),
+        AST::Atom(Atom::Value(Value::Nil)) => {
+            let implicit = ["x", "y", "z"];
+            let ids: Vec<Option<u16>> = implicit.iter().map(|n| arena.name_id(n)).collect();
+            match valence(r, &ids) {
+                0 | 1 => Atom::Lambda(None, arena.intern_ast("x".to_string()), r),
+                n => {
+                    let names = implicit[..n].iter().map(|n| arena.intern(n.to_string())).collect();
+                    Atom::Lambda(None, arena.vec(names), r)
+                }
+            }
+        }
         _ => Atom::Lambda(None, l, r),
     })
 }
 
+pub fn bools<'a>(s: &str) -> AST<'a> {
+    // 1b is an atom, 0101b is a boolean vector
+    let v: Vec<bool> = s.chars().map(|c| c == '1').collect();
+    AST::Atom(Atom::Value(if v.len() == 1 { Value::Bool(v[0]) } else { Value::VecBool(v) }))
+}
+
+pub fn bytes<'a>(s: &str) -> AST<'a> {
+    // 0x0a is an atom, 0x0a0b is a byte vector, odd digits are left padded
+    let s = if s.len() % 2 == 1 { format!("0{}", s) } else { s.to_string() };
+    let v: Vec<u8> = (0..s.len() / 2).map(|i| u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap()).collect();
+    AST::Atom(Atom::Value(if v.len() == 1 { Value::Byte(v[0]) } else { Value::VecByte(v) }))
+}
+
 pub fn table<'a>(l: &'a AST<'a>, r: &'a AST<'a>, arena: &'a Arena<'a>) -> AST<'a> {
     AST::Atom(Atom::Table(l, r))
 }
@@ -760,15 +968,15 @@ pub fn verb<'a>(v: Verb, l: &'a AST<'a>, r: &'a AST<'a>, arena: &'a Arena<'a>) -
             })
         }
         Verb::Cast => {
-            let rexpr = match r {
-                &AST::Atom(Atom::Dict(&AST::Vector(ref x))) if x.len() == 3 => Atom::Cond(&x[0], &x[1], &x[2]),
-                _ => Atom::Verb(v, l, r),
-            };
-            AST::Atom(match *l {
-                AST::Atom(Atom::Value(Value::Nil)) => rexpr,
-                _ => Atom::Call(l, arena.ast(AST::Atom(rexpr))), 
+            AST::Atom(match (l, r) {
+                (&AST::Atom(Atom::Value(Value::Nil)), &AST::Atom(Atom::Dict(&AST::Vector(ref x)))) if x.len() == 3 => {
+                    Atom::Cond(&x[0], &x[1], &x[2])
+                }
+                (_, &AST::Atom(Atom::Dict(&AST::Vector(ref x)))) if x.len() == 3 => {
+                    Atom::Call(l, arena.ast(AST::Atom(Atom::Cond(&x[0], &x[1], &x[2]))))
+                }
+                _ => Atom::Verb(v, l, r), // `type$value
             })
-
         }
         _ => {
             AST::Atom(match r { // optional AST transformations could be done during parsing
@@ -813,14 +1021,14 @@ impl<'a> ASTAcc<'a> {
         ASTAcc { inner: Rc::new(UnsafeCell::new(a)) }
     }
 
-    pub fn push(&'a self, n: &'a AST<'a>) -> usize {
+    pub fn push(&self, n: &AST<'a>) -> usize {
         unsafe {
             (*self.inner.deref().get()).push(n.clone());
             self.len() - 1
         }
     }
 
-    pub fn set(&'a self, idx: usize, val: AST<'a>) {
+    pub fn set(&self, idx: usize, val: AST<'a>) {
         unsafe {
             (&mut *self.inner.deref().get())[idx] = val;
         }
@@ -830,11 +1038,11 @@ impl<'a> ASTAcc<'a> {
         unsafe { &*self.inner.deref().get() }
     }
 
-    pub fn len(&'a self) -> usize {
+    pub fn len(&self) -> usize {
         unsafe { (*self.inner.deref().get()).len() }
     }
 
-    pub fn disown(&'a self) -> Vec<AST<'a>> {
+    pub fn disown(&self) -> Vec<AST<'a>> {
         // transfer vector ownership
         unsafe { mem::replace(&mut *self.inner.deref().get(), vec![]) }
     }
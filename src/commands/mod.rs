@@ -1,3 +1,3 @@
-
 pub mod ast;
 pub mod command;
+pub mod source;
@@ -0,0 +1,163 @@
+// O source printer, the inverse of parse. Names, symbols and sequences
+// are resolved through the arena, so the text can be parsed by another
+// interpreter (e.g. on a remote core).
+
+use commands::ast::*;
+use streams::verb::num;
+
+pub fn verb(v: Verb) -> String {
+    match v {
+        Verb::Plus => "+",
+        Verb::Minus => "-",
+        Verb::Times => "*",
+        Verb::Divide => "%",
+        Verb::Mod => "!",
+        Verb::Min => "&",
+        Verb::Max => "|",
+        Verb::Lt => "<",
+        Verb::Le => "<=",
+        Verb::Gt => ">",
+        Verb::Ge => ">=",
+        Verb::Eq => "=",
+        Verb::NEq => "<>",
+        Verb::Match => "~",
+        Verb::Concat => ",",
+        Verb::Except => "^",
+        Verb::Take => "#",
+        Verb::Drop => "_",
+        Verb::Cast => "$",
+        Verb::Find => "?",
+        Verb::At => "@",
+        Verb::Dot => ".",
+        x => return format!("{:?}", x),
+    }
+    .to_string()
+}
+
+pub fn adverb(a: Adverb) -> String {
+    match a {
+        Adverb::Each => "'",
+        Adverb::EachPrio => "':",
+        Adverb::EachLeft => "\\:",
+        Adverb::EachRight => "/:",
+        Adverb::Over => "/",
+        Adverb::Scan => "\\",
+        Adverb::Assign => ":",
+        Adverb::View => "::",
+        _ => ";",
+    }
+    .to_string()
+}
+
+fn int(n: i64, suffix: &str, cast: &str) -> String {
+    // there are no negative literals, narrow widths are cast from long
+    match (n, suffix) {
+        (NULL_INT, "") => "0N".to_string(),
+        (NULL_INT, _) => format!("`{}$0N", cast),
+        (n, "") if n < 0 => format!("0-{}", -n),
+        (n, _) if n < 0 => format!("`{}$0-{}", cast, -n),
+        (n, s) => format!("{}{}", n, s),
+    }
+}
+
+fn float(n: f64, suffix: &str) -> String {
+    if n.is_nan() {
+        return if suffix == "" { "0n".to_string() } else { "`e$0n".to_string() };
+    }
+    let s = format!("{}{}", n.abs(), if n.fract() == 0.0 && suffix == "" { ".0" } else { "" });
+    if n < 0.0 {
+        format!("0-{}{}", s, suffix)
+    } else {
+        format!("{}{}", s, suffix)
+    }
+}
+
+fn items(v: &Value, arena: &Arena) -> Option<Vec<String>> {
+    v.items().map(|x| x.iter().map(|i| value(i, arena)).collect())
+}
+
+pub fn value(v: &Value, arena: &Arena) -> String {
+    match *v {
+        Value::Nil => "".to_string(),
+        Value::SymbolInt(s) => format!("`{}", arena.symbol_name(s).unwrap_or(String::new())),
+        Value::SequenceInt(s) => format!("\"{}\"", arena.sequence_name(s).unwrap_or(String::new())),
+        Value::Bool(b) => format!("{}b", b as u8),
+        Value::Byte(b) => format!("0x{:02x}", b),
+        Value::Short(n) => int(num::conv(n), "h", "h"),
+        Value::Int32(n) => int(num::conv(n), "i", "i"),
+        Value::Number(n) => int(n, "", "j"),
+        Value::Real(n) => float(n as f64, "e"),
+        Value::Float(n) => float(n, ""),
+        Value::Ioverb(ref s) => s.clone(),
+        ref x => format!("({})", items(x, arena).unwrap_or(vec![]).join(";")),
+    }
+}
+
+fn exprs(n: &AST, arena: &Arena) -> String {
+    match n {
+        &AST::Vector(ref v) => v.iter().map(|x| ast(x, arena)).collect::<Vec<String>>().join(";"),
+        &AST::Atom(Atom::Value(ref v)) if v.is_vector() => items(v, arena).unwrap_or(vec![]).join(";"),
+        x => ast(x, arena),
+    }
+}
+
+pub fn ast(n: &AST, arena: &Arena) -> String {
+    match n {
+        &AST::Vector(_) => format!("[{}]", exprs(n, arena)),
+        &AST::Atom(ref a) => {
+            match a {
+                &Atom::Value(ref v) => value(v, arena),
+                &Atom::NameInt(s) => arena.name(s).unwrap_or(String::new()),
+                &Atom::List(x) => format!("({})", exprs(x, arena)),
+                &Atom::Dict(x) => format!("[{}]", exprs(x, arena)),
+                &Atom::Call(f, a @ &AST::Atom(Atom::Dict(_))) => format!("{}{}", ast(f, arena), ast(a, arena)),
+                &Atom::Call(f, a) => format!("{} {}", ast(f, arena), ast(a, arena)),
+                &Atom::Assign(a, b) => format!("{}:{}", ast(a, arena), ast(b, arena)),
+                &Atom::Cond(c, a, b) => format!("$[{};{};{}]", ast(c, arena), ast(a, arena), ast(b, arena)),
+                &Atom::Verb(v, l, r) => format!("{}{}{}", ast(l, arena), verb(v), ast(r, arena)),
+                &Atom::Adverb(v, l, r) => format!("{}{}{}", ast(l, arena), adverb(v), ast(r, arena)),
+                &Atom::Table(a, b) => format!("([{}]{})", exprs(a, arena), exprs(b, arena)),
+                &Atom::Lambda(_, args, body) => {
+                    match args {
+                        &AST::Atom(Atom::Value(Value::Nil)) => format!("{{{}}}", exprs(body, arena)),
+                        x => format!("{{[{}]{}}}", exprs(x, arena), exprs(body, arena)),
+                    }
+                }
+                _ => "".to_string(),
+            }
+        }
+    }
+}
+
+pub fn names(n: &AST, acc: &mut Vec<u16>) {
+    // all names a piece of code refers to
+    match n {
+        &AST::Vector(ref v) => {
+            for x in v {
+                names(x, acc)
+            }
+        }
+        &AST::Atom(Atom::NameInt(s)) => {
+            if !acc.contains(&s) {
+                acc.push(s)
+            }
+        }
+        &AST::Atom(Atom::List(a)) |
+        &AST::Atom(Atom::Dict(a)) => names(a, acc),
+        &AST::Atom(Atom::Call(a, b)) |
+        &AST::Atom(Atom::Assign(a, b)) |
+        &AST::Atom(Atom::Table(a, b)) |
+        &AST::Atom(Atom::Lambda(_, a, b)) |
+        &AST::Atom(Atom::Verb(_, a, b)) |
+        &AST::Atom(Atom::Adverb(_, a, b)) => {
+            names(a, acc);
+            names(b, acc)
+        }
+        &AST::Atom(Atom::Cond(a, b, c)) => {
+            names(a, acc);
+            names(b, acc);
+            names(c, acc)
+        }
+        _ => (),
+    }
+}
@@ -1,38 +1,327 @@
 use queues::publisher::Publisher;
 use queues::publisher::Subscriber;
-use core::cell::UnsafeCell;
+use core::cell::{Cell, UnsafeCell};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{fence, AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::thread;
+use io::notify::Notify;
 use intercore::message::Message;
+use commands::ast::Value;
+use sys;
 
 pub fn send<'a>(bus: &'a Channel, m: Message) {
-    if let Some(v) = bus.publisher.next() {
-        *v = m;
-        bus.publisher.commit();
+    // older messages waiting in the outbox go first
+    let m = match bus.flush() {
+        true => {
+            match bus.publish(m) {
+                Ok(()) => return,
+                Err(m) => m,
+            }
+        }
+        false => m,
+    };
+    bus.hold(m)
+}
+
+pub fn force<'a>(bus: &'a Channel, m: Message) {
+    // control messages wait in the outbox whatever the overflow policy says
+    let m = match bus.flush() {
+        true => {
+            match bus.publish(m) {
+                Ok(()) => return,
+                Err(m) => m,
+            }
+        }
+        false => m,
     };
+    unsafe { &mut *bus.outbox.get() }.push_back(m)
+}
+
+fn control(m: &Message) -> bool {
+    match *m {
+        Message::Halt(_) => true,
+        _ => false,
+    }
+}
+
+// What happens to a message when the bus is full
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Overflow {
+    Block, // waits in the outbox until the readers catch up, the sender waits while it is full
+    DropOldest, // the outbox keeps the newest `limit` messages
+    Error, // the requesting task fails
+}
+
+impl Overflow {
+    pub fn from_name(s: &str) -> Option<Overflow> {
+        match s {
+            "block" => Some(Overflow::Block),
+            "drop" => Some(Overflow::DropOldest),
+            "error" => Some(Overflow::Error),
+            _ => None,
+        }
+    }
+}
+
+pub const OUTBOX: usize = 1024;
+
+// ms a Block sender waits for room before its message is refused, so two cores whose
+// outboxes are full can't wait on each other forever
+pub const WAIT: u64 = 100;
+
+#[derive(Debug, Default)]
+pub struct Traffic {
+    pub sent: Cell<u64>,
+    pub retried: Cell<u64>, // sent from the outbox
+    pub dropped: Cell<u64>,
+    pub lost: Cell<u64>, // dropped with no local task to tell, the requester times out
+}
+
+fn bump(c: &Cell<u64>) {
+    c.set(c.get() + 1)
+}
+
+// A core sleeping on its bell is woken by any channel it listens to
+
+#[derive(Clone)]
+pub struct Bell(Arc<(Notify, AtomicBool)>);
+
+impl Bell {
+    pub fn new() -> Self {
+        Bell(Arc::new((Notify::new(), AtomicBool::new(false))))
+    }
+
+    pub fn notify(&self) -> &Notify {
+        &(self.0).0
+    }
+
+    pub fn ring(&self) {
+        // the syscall is paid only when the core really sleeps
+        fence(Ordering::SeqCst);
+        if (self.0).1.load(Ordering::SeqCst) {
+            (self.0).0.send()
+        }
+    }
+
+    pub fn park(&self) {
+        // published before the sleeper looks at its channels one last time
+        (self.0).1.store(true, Ordering::SeqCst);
+        fence(Ordering::SeqCst)
+    }
+
+    pub fn unpark(&self) {
+        (self.0).1.store(false, Ordering::SeqCst);
+        (self.0).0.wait() // drains the eventfd
+    }
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum TypeId {
     Byte,
     Int,
     Float,
+    Symbol,
+    Vector,
+    Any,
+}
+
+impl TypeId {
+    pub fn from_name(s: &str) -> Option<TypeId> {
+        // queue types are named by their O type letter
+        match s {
+            "x" => Some(TypeId::Byte),
+            "j" => Some(TypeId::Int),
+            "f" => Some(TypeId::Float),
+            "s" => Some(TypeId::Symbol),
+            "v" => Some(TypeId::Vector),
+            "a" => Some(TypeId::Any),
+            _ => None,
+        }
+    }
+
+    pub fn from_u8(x: u8) -> Option<TypeId> {
+        match x {
+            0 => Some(TypeId::Byte),
+            1 => Some(TypeId::Int),
+            2 => Some(TypeId::Float),
+            3 => Some(TypeId::Symbol),
+            4 => Some(TypeId::Vector),
+            5 => Some(TypeId::Any),
+            _ => None,
+        }
+    }
+
+    pub fn admits(&self, v: &Value) -> bool {
+        match (*self, v) {
+            (TypeId::Byte, &Value::Byte(_)) |
+            (TypeId::Byte, &Value::VecByte(_)) |
+            (TypeId::Int, &Value::Number(_)) |
+            (TypeId::Float, &Value::Float(_)) |
+            (TypeId::Symbol, &Value::SymbolInt(_)) |
+            (TypeId::Any, _) => true,
+            (TypeId::Vector, v) => v.is_vector(),
+            _ => false,
+        }
+    }
+
+    pub fn serialised(&self) -> bool {
+        // interned ids mean nothing to another interpreter, such slots hold wire bytes.
+        // General lists may carry symbols too.
+        *self == TypeId::Symbol || *self == TypeId::Vector || *self == TypeId::Any
+    }
 }
 
 pub struct Memory {
-    publishers: UnsafeCell<Vec<Publisher<i64>>>,
-    subscribers: UnsafeCell<Vec<Subscriber<i64>>>,
+    publishers: UnsafeCell<Vec<Publisher<Value>>>,
+    subscribers: UnsafeCell<Vec<Subscriber<Value>>>,
+    pub_types: UnsafeCell<Vec<TypeId>>,
+    sub_types: UnsafeCell<Vec<TypeId>>,
+    readers: UnsafeCell<Vec<Vec<Bell>>>, // of the other cores subscribed to each publisher
 }
 
 pub struct Channel {
     pub id: usize,
     pub publisher: Publisher<Message>,
     pub subscribers: Vec<Subscriber<Message>>,
+    pub peers: Vec<usize>, // the core behind each subscriber
+    pub relay: Vec<usize>, // cores reached through this one
+    pub overflow: Overflow,
+    pub limit: usize,
+    pub wait: Duration,
+    pub traffic: Traffic,
+    pub bell: Bell, // this core's
+    pub rings: Vec<Bell>, // of the cores listening here
+    pub bells: Vec<Bell>, // of every core by id, for the readers of data queues
+    outbox: UnsafeCell<VecDeque<Message>>,
+    failed: UnsafeCell<Vec<(usize, usize, String)>>,
 }
 
 impl Channel {
     pub fn new(id: usize, cap: usize) -> Self {
         Channel {
             id: id,
-            publisher: Publisher::with_capacity(cap),
+            publisher: Publisher::with_pages(cap), // bound to the core's node as whole pages
             subscribers: Vec::new(),
+            peers: Vec::new(),
+            relay: Vec::new(),
+            overflow: Overflow::Block,
+            limit: OUTBOX,
+            wait: Duration::from_millis(WAIT),
+            traffic: Traffic::default(),
+            bell: Bell::new(),
+            rings: Vec::new(),
+            bells: Vec::new(),
+            outbox: UnsafeCell::new(VecDeque::new()),
+            failed: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    fn publish(&self, m: Message) -> Result<(), Message> {
+        match self.publisher.next() {
+            Some(v) => {
+                *v = m;
+                self.publisher.commit();
+                bump(&self.traffic.sent);
+                for b in &self.rings {
+                    b.ring()
+                }
+                Ok(())
+            }
+            None => Err(m),
+        }
+    }
+
+    pub fn flush(&self) -> bool {
+        // true once the outbox is empty
+        let outbox = unsafe { &mut *self.outbox.get() };
+        while let Some(m) = outbox.pop_front() {
+            if let Err(m) = self.publish(m) {
+                outbox.push_front(m);
+                return false;
+            }
+            bump(&self.traffic.retried);
+        }
+        true
+    }
+
+    fn hold(&self, m: Message) {
+        let outbox = unsafe { &mut *self.outbox.get() };
+        match self.overflow {
+            Overflow::Block => {
+                let start = Instant::now();
+                while outbox.len() >= self.limit && !self.flush() && start.elapsed() < self.wait {
+                    thread::yield_now();
+                }
+                match outbox.len() < self.limit {
+                    true => outbox.push_back(m),
+                    false => self.refuse(m),
+                }
+            }
+            Overflow::DropOldest => {
+                if outbox.len() >= self.limit {
+                    if let Some(k) = outbox.iter().position(|x| !control(x)) {
+                        outbox.remove(k);
+                        bump(&self.traffic.dropped);
+                    }
+                }
+                outbox.push_back(m)
+            }
+            Overflow::Error => {
+                // replies and relays have nobody local to tell, they wait while there is room
+                match m.requester() {
+                    Some((from, _, _)) if from == self.id => self.refuse(m),
+                    _ if outbox.len() < self.limit => outbox.push_back(m),
+                    _ => self.refuse(m),
+                }
+            }
+        }
+    }
+
+    fn refuse(&self, m: Message) {
+        // a local task that asked fails, anything else is lost
+        match m.requester() {
+            Some((from, task_id, corr)) if from == self.id => {
+                let failed = unsafe { &mut *self.failed.get() };
+                failed.push((task_id, corr, format!("bus {} is full", self.id)));
+            }
+            _ => bump(&self.traffic.lost),
+        }
+        bump(&self.traffic.dropped)
+    }
+
+    pub fn queued(&self) -> usize {
+        unsafe { &*self.outbox.get() }.len()
+    }
+
+    pub fn failures(&self) -> Vec<(usize, usize, String)> {
+        let failed = unsafe { &mut *self.failed.get() };
+        failed.drain(..).collect()
+    }
+
+    pub fn bind(&self, node: usize) -> bool {
+        // the ring this core writes lives on its NUMA node
+        let (p, len) = self.publisher.memory();
+        sys::bind_memory(p, len, node)
+    }
+
+    pub fn listen(&mut self, peer: usize, s: Subscriber<Message>) {
+        self.subscribers.push(s);
+        self.peers.push(peer);
+    }
+
+    pub fn heard(&self) -> bool {
+        // something is waiting to be read, checked before going to sleep
+        self.subscribers.iter().any(|s| s.ready(1))
+    }
+
+    pub fn forwards(&self, k: usize, m: &Message) -> bool {
+        // a message heard from another core, addressed to a core behind this one
+        match (self.peers.get(k), m.to()) {
+            (Some(&p), Some(to)) => p != self.id && to != self.id && self.relay.contains(&to),
+            _ => false,
         }
     }
 }
@@ -42,15 +331,54 @@ impl Memory {
         Memory {
             publishers: UnsafeCell::new(vec![]),
             subscribers: UnsafeCell::new(vec![]),
+            pub_types: UnsafeCell::new(vec![]),
+            sub_types: UnsafeCell::new(vec![]),
+            readers: UnsafeCell::new(vec![]),
         }
     }
     #[inline]
-    pub fn publishers(&self) -> &mut Vec<Publisher<i64>> {
+    pub fn publishers(&self) -> &mut Vec<Publisher<Value>> {
         unsafe { &mut *self.publishers.get() }
     }
 
     #[inline]
-    pub fn subscribers(&self) -> &mut Vec<Subscriber<i64>> {
+    pub fn subscribers(&self) -> &mut Vec<Subscriber<Value>> {
         unsafe { &mut *self.subscribers.get() }
     }
-}
\ No newline at end of file
+
+    pub fn add_publisher(&self, p: Publisher<Value>, ty: TypeId) -> usize {
+        self.publishers().push(p);
+        unsafe { &mut *self.pub_types.get() }.push(ty);
+        unsafe { &mut *self.readers.get() }.push(vec![]);
+        self.publishers().len() - 1
+    }
+
+    pub fn wake(&self, pub_id: usize, bell: Bell) {
+        // the core behind a subscriber is rung whenever the publisher commits
+        if let Some(r) = unsafe { &mut *self.readers.get() }.get_mut(pub_id) {
+            r.push(bell)
+        }
+    }
+
+    pub fn ring(&self, pub_id: usize) {
+        if let Some(r) = unsafe { &*self.readers.get() }.get(pub_id) {
+            for b in r {
+                b.ring()
+            }
+        }
+    }
+
+    pub fn add_subscriber(&self, s: Subscriber<Value>, ty: TypeId) -> usize {
+        self.subscribers().push(s);
+        unsafe { &mut *self.sub_types.get() }.push(ty);
+        self.subscribers().len() - 1
+    }
+
+    pub fn publisher_type(&self, id: usize) -> Option<TypeId> {
+        unsafe { &*self.pub_types.get() }.get(id).cloned()
+    }
+
+    pub fn subscriber_type(&self, id: usize) -> Option<TypeId> {
+        unsafe { &*self.sub_types.get() }.get(id).cloned()
+    }
+}
@@ -2,12 +2,31 @@
 use commands::ast::{Error, AST, Atom, Arena, Value};
 use streams::otree;
 use streams::interpreter::{Interpreter, Lazy, Cont};
-use intercore::message::{Pub, Sub, Message, Spawn};
+use intercore::message::{Pub, Sub, Message, Spawn, Join, Call, Lookup, Names, After, Cancel, QoS, Kill, Tasks,
+                         Watch, Watcher, Supervise, Child, Metrics, Halt};
+use reactors::runq::NORMAL;
+use reactors::scheduler::ZTS;
+use reactors::sup::Strategy;
+use intercore::server::REGISTRAR;
+use commands::source;
+use intercore::bus::TypeId;
+use intercore::wire::{self, Reader};
 use reactors::task::Context;
 use handle::{into_raw, from_raw};
+use std::time::{Instant, Duration};
 
 // The InterCore messages + Buildins are being handled in Interpreter
 
+// bad arguments fail the call with an EvalError instead of taking the core down
+macro_rules! arg {
+    ($i:expr, $e:expr) => {
+        match $e {
+            Ok(x) => x,
+            Err(e) => return Context::Fail($i.task_id, e),
+        }
+    }
+}
+
 pub fn internals<'a>(i: &'a mut Interpreter<'a>, f_id: u16, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
     match f_id {
         0 => print(i, args, arena),
@@ -16,7 +35,24 @@ pub fn internals<'a>(i: &'a mut Interpreter<'a>, f_id: u16, args: &'a AST<'a>, a
         3 => send(i, args, arena),
         4 => receive(i, args, arena),
         5 => spawn(i, args, arena),
-        6 => Context::Nil,
+        6 => wait(i, args, arena),
+        7 => call(i, args, arena),
+        8 => try_receive(i, args, arena),
+        9 => receive_all(i, args, arena),
+        10 => names(i, args, arena),
+        11 => after(i, args, arena, false),
+        12 => after(i, args, arena, true),
+        13 => cancel(i, args, arena),
+        14 => qos(i, args, arena),
+        15 => kill(i, args, arena),
+        16 => tasks(i, args, arena),
+        17 => supervise(i, args, arena),
+        18 => child(i, args, arena),
+        19 => watch(i, args, arena, false),
+        20 => watch(i, args, arena, true),
+        21 => stats(i, args, arena),
+        22 => exit(i, args, arena),
+        23 => receive_n(i, args, arena),
         _ => panic!("unknown internal func"),
     }
 }
@@ -40,6 +76,12 @@ pub fn eval_context<'a>(f: otree::NodeId,
                                  from_raw(h).arena.cont(Cont::Intercore(message.clone(), cont)))
         }
         Context::Node(ref ast) => from_raw(h).run_cont(f, ast, cont),
+        Context::Fail(_, e) => {
+            Err(Error::EvalError {
+                desc: e,
+                ast: String::new(),
+            })
+        }
 
         _ => panic!("TODO"),
     }
@@ -53,36 +95,380 @@ pub fn print<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena
 
 pub fn spawn<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
     println!("Spawn Args: {:?}", args);
-    let (core, txt) = (0, "a:1".to_string());
-
-    let (core, txt) = match args {
-        &AST::Vector(ref v) if v.len() == 2 => {
-            match (&v[0], &v[1]) {
-                (&AST::Atom(Atom::Value(Value::Number(c))), &AST::Atom(Atom::Value(Value::SequenceInt(n)))) => {
-                    (c, "test".to_string())
+    // spawn[core;f;prio;`pin] keeps the task off idle cores looking for work
+    let (v, pin) = match args {
+        &AST::Vector(ref v) if v.len() == 4 => {
+            match &v[3] {
+                &AST::Atom(Atom::Value(Value::SymbolInt(s))) if arena.symbol_name(s) == Some("pin".to_string()) => {
+                    (&v[..3], true)
                 }
-                _ => (0, "".to_string()),
+                _ => return Context::Fail(i.task_id, "spawn expects `pin last".to_string()),
             }
         }
-        _ => (0, "".to_string()),
+        &AST::Vector(ref v) => (&v[..], false),
+        _ => return Context::Fail(i.task_id, "spawn expects [core;f]".to_string()),
     };
+    let h = into_raw(i);
+    let (core, txt, prio) = arg!(from_raw(h), target(from_raw(h), v, arena));
 
+    let i = from_raw(h);
+    i.corr += 1;
     i.edge = Message::Spawn(Spawn {
-        from: 0,
-        to: core as usize,
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+        prio: prio.unwrap_or(NORMAL),
+        pin: pin,
+        txt: txt,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn call<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    let h = into_raw(i);
+    let (core, txt) = match arg!(from_raw(h), remote(from_raw(h), args, arena)) {
+        (c, t, None) => (c, t),
+        _ => return Context::Fail(from_raw(h).task_id, "call takes no priority".to_string()),
+    };
+
+    let i = from_raw(h);
+    i.corr += 1;
+    i.edge = Message::Call(Call {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+        txt: txt,
+    });
+    Context::Intercore(&i.edge)
+}
+
+fn remote<'a>(i: &'a Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Result<(usize, String, Option<u8>), String> {
+    match args {
+        &AST::Vector(ref v) => target(i, &v[..], arena),
+        _ => Err("expected [core;code]".to_string()),
+    }
+}
+
+fn target<'a>(i: &'a Interpreter<'a>, v: &'a [AST<'a>], arena: &'a Arena<'a>) -> Result<(usize, String, Option<u8>), String> {
+    // [core; "code"] or [core; lambda], with an optional priority last
+    if v.len() != 2 && v.len() != 3 {
+        return Err("expected [core;code] or [core;code;prio]".to_string());
+    }
+    let prio = match v.get(2) {
+        Some(&AST::Atom(Atom::Value(Value::Number(p)))) if p >= 0 && p < 256 => Some(p as u8),
+        Some(_) => return Err("priority is 0..255".to_string()),
+        None => None,
+    };
+    match (&v[0], &v[1]) {
+        (&AST::Atom(Atom::Value(Value::Number(c))), &AST::Atom(Atom::Value(Value::SequenceInt(n)))) if c >= 0 => {
+            match arena.sequence_name(n) {
+                Some(txt) => Ok((c as usize, txt, prio)),
+                None => Err("unknown sequence".to_string()),
+            }
+        }
+        (&AST::Atom(Atom::Value(Value::Number(c))), f @ &AST::Atom(Atom::Lambda(..))) if c >= 0 => {
+            Ok((c as usize, closure(i, f, arena), prio))
+        }
+        _ => Err("expected [core;code] or [core;code;prio]".to_string()),
+    }
+}
+
+pub fn supervise<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    // sup[`oneforone;intensity;ms] supervises from the calling task on this core
+    let (strategy, intensity, ms) = match args {
+        &AST::Vector(ref v) if v.len() == 3 => {
+            match (&v[0], &v[1], &v[2]) {
+                (&AST::Atom(Atom::Value(Value::SymbolInt(s))),
+                 &AST::Atom(Atom::Value(Value::Number(n))),
+                 &AST::Atom(Atom::Value(Value::Number(ms)))) if n >= 0 && ms >= 0 => {
+                    let name = arena.symbol_name(s).unwrap_or(String::new());
+                    match Strategy::from_name(&name) {
+                        Some(strategy) => (strategy, n as usize, ms as usize),
+                        None => return Context::Fail(i.task_id, format!("{}: unknown strategy", name)),
+                    }
+                }
+                _ => return Context::Fail(i.task_id, "sup expects [strategy;intensity;ms]".to_string()),
+            }
+        }
+        _ => return Context::Fail(i.task_id, "sup expects [strategy;intensity;ms]".to_string()),
+    };
+
+    i.corr += 1;
+    i.edge = Message::Supervise(Supervise {
+        from: i.core,
+        to: i.core,
+        task_id: i.task_id,
+        corr: i.corr,
+        strategy: strategy as u8,
+        intensity: intensity,
+        ms: ms,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn child<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    // child[s;core;f] or child[s;core;f;prio] answers the child's number
+    let h = into_raw(i);
+    let (sup, (core, txt, prio)) = match args {
+        &AST::Vector(ref v) if v.len() > 1 => {
+            match &v[0] {
+                &AST::Atom(Atom::Value(Value::Number(s))) if s >= 0 => {
+                    (s as usize, arg!(from_raw(h), target(from_raw(h), &v[1..], arena)))
+                }
+                _ => return Context::Fail(from_raw(h).task_id, "child expects a supervisor".to_string()),
+            }
+        }
+        _ => return Context::Fail(from_raw(h).task_id, "child expects [sup;core;f] or [sup;core;f;prio]".to_string()),
+    };
+
+    let i = from_raw(h);
+    i.corr += 1;
+    i.edge = Message::Child(Child {
+        from: i.core,
+        to: i.core,
+        task_id: i.task_id,
+        corr: i.corr,
+        sup: sup,
+        core: core,
+        prio: prio.unwrap_or(NORMAL),
         txt: txt,
     });
     Context::Intercore(&i.edge)
 }
 
+pub fn watch<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>, monitor: bool) -> Context<'a> {
+    // link[h] crashes the caller and h together, monitor[h;p] puts [core;pid;crashed] into the vector queue p
+    let ((core, pid), by) = match args {
+        &AST::Vector(ref v) if monitor && v.len() == 2 => {
+            match &v[1] {
+                &AST::Atom(Atom::Value(Value::Number(p))) if p >= 0 => {
+                    (arg!(i, handle(&v[0])), Watcher::Monitor(i.core, p as usize))
+                }
+                _ => return Context::Fail(i.task_id, "monitor expects a queue".to_string()),
+            }
+        }
+        _ if !monitor => (arg!(i, handle(args)), Watcher::Link(i.core, i.task_id)),
+        _ => return Context::Fail(i.task_id, "monitor expects [(core;pid);queue]".to_string()),
+    };
+
+    i.corr += 1;
+    i.edge = Message::Watch(Watch {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+        pid: pid,
+        by: by,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn qos<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    // qos[(core;pid);prio] answers the priority the task had
+    let ((core, pid), prio) = arg!(i, priority(args));
+
+    i.corr += 1;
+    i.edge = Message::QoS(QoS {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+        pid: pid,
+        prio: prio,
+    });
+    Context::Intercore(&i.edge)
+}
+
+fn exit_code<'a>(args: &'a AST<'a>) -> Result<u8, String> {
+    // 0 when none is given
+    let code = match args {
+        &AST::Atom(Atom::Value(Value::Number(c))) => c,
+        &AST::Vector(ref v) if v.len() == 1 => {
+            match &v[0] {
+                &AST::Atom(Atom::Value(Value::Number(c))) => c,
+                _ => return Err("exit expects a code".to_string()),
+            }
+        }
+        &AST::Atom(Atom::Value(Value::Nil)) => 0,
+        &AST::Atom(Atom::Value(_)) => return Err("exit expects a code".to_string()),
+        _ => 0,
+    };
+    if code < 0 || code > 255 {
+        return Err(format!("{}: exit codes go from 0 to 255", code));
+    }
+    Ok(code as u8)
+}
+
+fn priority<'a>(args: &'a AST<'a>) -> Result<((usize, usize), u8), String> {
+    // a task handle and a priority in 0..255
+    match args {
+        &AST::Vector(ref v) if v.len() == 2 => {
+            let h = try!(handle(&v[0]));
+            match &v[1] {
+                &AST::Atom(Atom::Value(Value::Number(p))) if p >= 0 && p < 256 => Ok((h, p as u8)),
+                _ => Err("expected a priority in 0..255".to_string()),
+            }
+        }
+        _ => Err("expected [(core;pid);prio]".to_string()),
+    }
+}
+
+fn handle<'a>(args: &'a AST<'a>) -> Result<(usize, usize), String> {
+    // a task is known by its (core;pid)
+    let h = match args {
+        &AST::Vector(ref v) if v.len() == 1 => &v[0],
+        x => x,
+    };
+    match h {
+        &AST::Atom(Atom::Value(Value::VecInt(ref h))) if h.len() == 2 && h[0] >= 0 && h[1] >= 0 => {
+            Ok((h[0] as usize, h[1] as usize))
+        }
+        _ => Err("expected a task handle (core;pid)".to_string()),
+    }
+}
+
+pub fn kill<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    let (core, pid) = arg!(i, handle(args));
+
+    i.corr += 1;
+    i.edge = Message::Kill(Kill {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+        pid: pid,
+    });
+    Context::Intercore(&i.edge)
+}
+
+fn core_arg<'a>(i: &Interpreter<'a>, args: &'a AST<'a>) -> usize {
+    // f[] on this core, f[core] on another
+    match args {
+        &AST::Atom(Atom::Value(Value::Number(c))) => c as usize,
+        &AST::Vector(ref v) if v.len() == 1 => {
+            match &v[0] {
+                &AST::Atom(Atom::Value(Value::Number(c))) => c as usize,
+                _ => i.core,
+            }
+        }
+        _ => i.core,
+    }
+}
+
+pub fn tasks<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    let core = core_arg(i, args);
+
+    i.corr += 1;
+    i.edge = Message::Tasks(Tasks {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn stats<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    // stats[] or stats[core], the counters of a core as (`name;value) rows
+    let core = core_arg(i, args);
+
+    i.corr += 1;
+    i.edge = Message::Metrics(Metrics {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn exit<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    // exit[] or exit[code], every core finalizes its tasks and the process ends with the code
+    let code = arg!(i, exit_code(args));
+
+    i.edge = Message::Halt(Halt {
+        from: i.core,
+        to: i.core,
+        code: code,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn wait<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    let (core, pid) = arg!(i, handle(args));
+
+    i.corr += 1;
+    i.edge = Message::Join(Join {
+        from: i.core,
+        to: core,
+        task_id: i.task_id,
+        corr: i.corr,
+        pid: pid,
+    });
+    Context::Intercore(&i.edge)
+}
+
+fn closure<'a>(i: &'a Interpreter<'a>, f: &'a AST<'a>, arena: &'a Arena<'a>) -> String {
+    // a lambda travels as source, the values it refers to are assigned ahead of the call
+    let mut seen = vec![];
+    let mut defs = vec![];
+    capture(i, f, &mut seen, &mut defs, arena);
+    defs.push(format!("{}[]", source::ast(f, arena)));
+    defs.join(";")
+}
+
+fn capture<'a>(i: &'a Interpreter<'a>,
+               f: &'a AST<'a>,
+               seen: &mut Vec<u16>,
+               defs: &mut Vec<String>,
+               arena: &'a Arena<'a>) {
+    if let &AST::Atom(Atom::Lambda(node, args, body)) = f {
+        let node = node.unwrap_or(i.env.last());
+        let mut params = vec![];
+        let mut free = vec![];
+        source::names(args, &mut params);
+        source::names(body, &mut free);
+        for n in free {
+            if params.contains(&n) || seen.contains(&n) {
+                continue;
+            }
+            seen.push(n);
+            match i.env.get(n, node) {
+                Some((v @ &AST::Atom(Atom::Value(_)), _)) |
+                Some((v @ &AST::Vector(_), _)) => {
+                    defs.push(format!("{}:{}", source::ast(&AST::Atom(Atom::NameInt(n)), arena), source::ast(v, arena)))
+                }
+                Some((v @ &AST::Atom(Atom::Lambda(..)), _)) => {
+                    capture(i, v, seen, defs, arena);
+                    defs.push(format!("{}:{}", source::ast(&AST::Atom(Atom::NameInt(n)), arena), source::ast(v, arena)))
+                }
+                _ => (), // builtins are defined on every core
+            }
+        }
+    }
+}
+
 pub fn publisher<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
     println!("Pub Args: {:?}", args);
 
-    let (core, cap) = match args {
-        &AST::Vector(ref v) if v.len() == 2 => {
+    // pub[core;cap], pub[core;cap;`type] or pub[core;cap;`type;`name], where ` is the default type
+    let (core, cap, ty, name) = match args {
+        &AST::Vector(ref v) if v.len() >= 2 && v.len() <= 4 => {
+            let sym = |x: &AST| match x {
+                &AST::Atom(Atom::Value(Value::SymbolInt(s))) => arena.symbol_name(s).unwrap_or(String::new()),
+                _ => panic!("oops!"),
+            };
+            let ty = match v.get(2).map(|x| sym(x)) {
+                None => TypeId::Int,
+                Some(ref s) if s == "" => TypeId::Int,
+                Some(s) => arg!(i, TypeId::from_name(&s).ok_or(format!("Unknown queue type: `{}", s))),
+            };
+            let name = v.get(3).map(|x| sym(x)).unwrap_or(String::new());
             match (&v[0], &v[1]) {
                 (&AST::Atom(Atom::Value(Value::Number(core))), &AST::Atom(Atom::Value(Value::Number(cap)))) => {
-                    (core as usize, cap as usize)
+                    (core as usize, cap as usize, ty, name)
                 }
                 _ => panic!("oops!"),
             }
@@ -90,12 +476,16 @@ pub fn publisher<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a A
         _ => panic!("oops!"),
     };
 
+    i.corr += 1;
     i.edge = Message::Pub(Pub {
-        from: 0,
-        task_id: 0,
-        to: core,
-        name: "".to_string(),
+        from: i.core,
+        task_id: i.task_id,
+        corr: i.corr,
+        to: if name == "" { core } else { REGISTRAR },
+        core: core,
+        name: name,
         cap: cap,
+        ty: ty,
     });
     Context::Intercore(&i.edge)
 }
@@ -103,6 +493,23 @@ pub fn publisher<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a A
 pub fn subscriber<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
     println!("Sub Args: {:?}", args);
 
+    let name = match args {
+        &AST::Vector(ref v) if v.len() == 1 => &v[0],
+        x => x,
+    };
+    if let &AST::Atom(Atom::Value(Value::SymbolInt(s))) = name {
+        // sub[`name] asks the registrar where the publisher lives
+        i.corr += 1;
+        i.edge = Message::Lookup(Lookup {
+            from: i.core,
+            to: REGISTRAR,
+            task_id: i.task_id,
+            corr: i.corr,
+            name: arena.symbol_name(s).expect("unknown symbol"),
+        });
+        return Context::Intercore(&i.edge);
+    }
+
     let (core, pub_id) = match args {
         &AST::Vector(ref v) if v.len() == 2 => {
             match (&v[0], &v[1]) {
@@ -115,51 +522,279 @@ pub fn subscriber<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a
         _ => panic!("oops!"),
     };
 
+    i.corr += 1;
     i.edge = Message::Sub(Sub {
-        from: i.task_id,
+        from: i.core,
         task_id: i.task_id,
+        corr: i.corr,
         to: core,
         pub_id: pub_id,
     });
     Context::Intercore(&i.edge)
 }
 
+pub fn names<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    i.corr += 1;
+    i.edge = Message::Names(Names {
+        from: i.core,
+        to: REGISTRAR,
+        task_id: i.task_id,
+        corr: i.corr,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn after<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>, every: bool) -> Context<'a> {
+    // after[ms;f], every[ms;f] with f a lambda or "code", every[ms] runs .z.ts
+    let (ms, txt) = match args {
+        &AST::Vector(ref v) if v.len() == 2 => {
+            match (&v[0], &v[1]) {
+                (&AST::Atom(Atom::Value(Value::Number(ms))), &AST::Atom(Atom::Value(Value::SequenceInt(n)))) => {
+                    (ms, arg!(i, arena.sequence_name(n).ok_or(format!("{}: unknown sequence", n))))
+                }
+                (&AST::Atom(Atom::Value(Value::Number(ms))), f @ &AST::Atom(Atom::Lambda(..))) => {
+                    // runs in this task, so globals are seen as they are then
+                    (ms, format!("{}[]", source::ast(f, arena)))
+                }
+                _ => return Context::Fail(i.task_id, "a timer expects [ms;f] or [ms;\"code\"]".to_string()),
+            }
+        }
+        &AST::Vector(ref v) if v.len() == 1 && every => {
+            match &v[0] {
+                &AST::Atom(Atom::Value(Value::Number(ms))) => (ms, ZTS.to_string()),
+                _ => return Context::Fail(i.task_id, "every expects ms".to_string()),
+            }
+        }
+        &AST::Atom(Atom::Value(Value::Number(ms))) if every => (ms, ZTS.to_string()),
+        _ => return Context::Fail(i.task_id, "a timer expects [ms;f] or [ms;\"code\"]".to_string()),
+    };
+    if ms < 0 {
+        return Context::Fail(i.task_id, format!("{}: a timer can't go off in the past", ms));
+    }
+    if ms == 0 && every && txt != ZTS {
+        // every[ms] alone with 0 stops .z.ts, a code run every 0 ms would never let the core go
+        return Context::Fail(i.task_id, "every needs a period of at least 1 ms".to_string());
+    }
+
+    i.corr += 1;
+    i.edge = Message::After(After {
+        from: i.core,
+        to: i.core,
+        task_id: i.task_id,
+        corr: i.corr,
+        ms: ms as usize,
+        every: every,
+        txt: txt,
+    });
+    Context::Intercore(&i.edge)
+}
+
+pub fn cancel<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    let id = match args {
+        &AST::Vector(ref v) if v.len() == 1 => &v[0],
+        x => x,
+    };
+    let id = match id {
+        &AST::Atom(Atom::Value(Value::Number(id))) if id >= 0 => id as usize,
+        _ => return Context::Fail(i.task_id, "cancel expects a timer".to_string()),
+    };
+
+    i.corr += 1;
+    i.edge = Message::Cancel(Cancel {
+        from: i.core,
+        to: i.core,
+        task_id: i.task_id,
+        corr: i.corr,
+        id: id,
+    });
+    Context::Intercore(&i.edge)
+}
+
 pub fn send<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
     println!("Send Args: {:?}", args);
 
     let (val, pub_id) = match args {
         &AST::Vector(ref v) if v.len() == 2 => {
             match (&v[0], &v[1]) {
-                (&AST::Atom(Atom::Value(Value::Number(pub_id))), &AST::Atom(Atom::Value(Value::Number(val)))) => {
-                    (val, pub_id)
-                }
+                (&AST::Atom(Atom::Value(Value::Number(pub_id))), &AST::Atom(Atom::Value(ref val))) => (val, pub_id as usize),
                 _ => panic!("oops!"),
             }
         }
         _ => panic!("oops!"),
     };
 
-    let mut p = i.queues.publishers().get(pub_id as usize).expect(&format!("Wrong publisher id: {}", pub_id));
-    if let Some(slot) = p.next() {
-        *slot = val;
-        p.commit();
+    let ty = arg!(i, i.queues.publisher_type(pub_id).ok_or(format!("Wrong publisher id: {}", pub_id)));
+    let items = match (ty, val) {
+        // a vector into a queue of its atoms is written as one contiguous slice
+        (TypeId::Int, &Value::VecInt(_)) |
+        (TypeId::Float, &Value::VecFloat(_)) => val.items().unwrap_or(vec![]),
+        _ if ty.admits(val) => vec![val.clone()],
+        _ => return Context::Fail(i.task_id, format!("{:?} queue {} can't take {}", ty, pub_id, val)),
+    };
+    let p = arg!(i, i.queues.publishers().get(pub_id).ok_or(format!("Wrong publisher id: {}", pub_id)));
+    match p.next_n_split(items.len()) {
+        Some((head, tail)) => {
+            for (slot, x) in head.iter_mut().chain(tail.iter_mut()).zip(items.iter()) {
+                *slot = pack(ty, x, arena);
+            }
+            p.commit();
+            i.queues.ring(pub_id);
+            Context::Node(arena.nil())
+        }
+        None => Context::Fail(i.task_id, format!("queue {} has no room for {} items", pub_id, items.len())),
     }
-    Context::Node(arena.nil())
 }
 
 pub fn receive<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
     println!("Receive Args: {:?}", args);
-    match args {
-        &AST::Atom(Atom::Value(Value::Number(sub_id))) => {
-            let mut s = i.queues.subscribers().get(sub_id as usize).expect(&format!("Wrong subscriber id: {}", sub_id));
-            if let Some(slot) = s.recv() {
-                let res = *slot;
-                s.commit();
-                return Context::Node(arena.ast(AST::Atom(Atom::Value(Value::Number(res as i64)))));
+
+    // rcv s is one atom, rcv[s;ms] gives up after ms
+    let (sub_id, ms) = match args {
+        &AST::Atom(Atom::Value(Value::Number(sub_id))) => (sub_id as usize, None),
+        &AST::Vector(ref v) if v.len() == 2 => {
+            match (&v[0], &v[1]) {
+                (&AST::Atom(Atom::Value(Value::Number(sub_id))), &AST::Atom(Atom::Value(Value::Number(ms)))) => {
+                    (sub_id as usize, Some(arg!(i, timeout(ms))))
+                }
+                _ => panic!("oops!"),
             }
         }
         _ => panic!("oops!"),
+    };
+    await_items(i, sub_id, None, ms, arena)
+}
+
+pub fn receive_n<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    // rcvn[s;n] is a vector of n, rcvn[s;n;ms] gives up after ms
+    let (sub_id, n, ms) = match args {
+        &AST::Vector(ref v) if v.len() == 2 || v.len() == 3 => {
+            let ms = match v.get(2) {
+                Some(&AST::Atom(Atom::Value(Value::Number(ms)))) => Some(arg!(i, timeout(ms))),
+                None => None,
+                _ => panic!("oops!"),
+            };
+            match (&v[0], &v[1]) {
+                (&AST::Atom(Atom::Value(Value::Number(sub_id))), &AST::Atom(Atom::Value(Value::Number(n)))) => {
+                    (sub_id as usize, n, ms)
+                }
+                _ => panic!("oops!"),
+            }
+        }
+        _ => panic!("oops!"),
+    };
+    // a batch the ring can't hold would never come
+    let cap = arg!(i,
+                   i.queues.subscribers().get(sub_id).map(|s| s.cap()).ok_or(format!("Wrong subscriber id: {}", sub_id)));
+    if n < 1 || n as usize > cap {
+        return Context::Fail(i.task_id, format!("{}: rcvn takes 1 to {} items", n, cap));
     }
+    await_items(i, sub_id, Some(n as usize), ms, arena)
+}
 
-    Context::Node(arena.nil())
+fn timeout(ms: i64) -> Result<u64, String> {
+    // ms a rcv waits at most
+    if ms < 0 {
+        return Err(format!("{}: a rcv can't time out in the past", ms));
+    }
+    Ok(ms as u64)
+}
+
+fn await_items<'a>(i: &'a mut Interpreter<'a>,
+                   sub_id: usize,
+                   n: Option<usize>,
+                   ms: Option<u64>,
+                   arena: &'a Arena<'a>)
+                   -> Context<'a> {
+    if let Some(v) = arg!(i, take(i, sub_id, n, arena)) {
+        i.blocked = None;
+        return Context::Node(v);
+    }
+    let count = n.unwrap_or(1);
+    let deadline = match i.blocked {
+        Some((s, c, d)) if s == sub_id && c == count => d, // still the same rcv, keep its deadline
+        _ => ms.map(|ms| Instant::now() + Duration::from_millis(ms)),
+    };
+    if deadline.map_or(false, |d| Instant::now() >= d) {
+        i.blocked = None;
+        return Context::Node(arena.nil());
+    }
+    i.blocked = Some((sub_id, count, deadline));
+    Context::Block
+}
+
+pub fn try_receive<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    match args {
+        &AST::Atom(Atom::Value(Value::Number(sub_id))) => {
+            Context::Node(arg!(i, take(i, sub_id as usize, None, arena)).unwrap_or(arena.nil()))
+        }
+        _ => Context::Fail(i.task_id, "trcv expects a subscriber".to_string()),
+    }
+}
+
+pub fn receive_all<'a>(i: &'a mut Interpreter<'a>, args: &'a AST<'a>, arena: &'a Arena<'a>) -> Context<'a> {
+    let sub_id = match args {
+        &AST::Atom(Atom::Value(Value::Number(sub_id))) => sub_id as usize,
+        _ => return Context::Fail(i.task_id, "rcvall expects a subscriber".to_string()),
+    };
+    let ty = arg!(i, i.queues.subscriber_type(sub_id).ok_or(format!("Wrong subscriber id: {}", sub_id)));
+    let s = arg!(i, i.queues.subscribers().get(sub_id).ok_or(format!("Wrong subscriber id: {}", sub_id)));
+    let items = match s.recv_all_split() {
+        Some((head, tail)) => {
+            let items = head.iter().chain(tail.iter()).map(|x| unpack(ty, x, arena)).collect();
+            s.commit();
+            items
+        }
+        None => vec![],
+    };
+    Context::Node(arena.ast(AST::Atom(Atom::Value(batch(ty, items)))))
+}
+
+fn take<'a>(i: &Interpreter<'a>,
+            sub_id: usize,
+            n: Option<usize>,
+            arena: &'a Arena<'a>)
+            -> Result<Option<&'a AST<'a>>, String> {
+    // None while the items are not there yet
+    let ty = try!(i.queues.subscriber_type(sub_id).ok_or(format!("Wrong subscriber id: {}", sub_id)));
+    let s = try!(i.queues.subscribers().get(sub_id).ok_or(format!("Wrong subscriber id: {}", sub_id)));
+    Ok(match s.recv_n_split(n.unwrap_or(1)) {
+        Some((head, tail)) => {
+            let mut items: Vec<Value> = head.iter().chain(tail.iter()).map(|x| unpack(ty, x, arena)).collect();
+            s.commit();
+            let res = match n {
+                Some(_) => batch(ty, items),
+                None => items.pop().unwrap_or(Value::Nil),
+            };
+            Some(arena.ast(AST::Atom(Atom::Value(res))))
+        }
+        None => None,
+    })
+}
+
+fn batch(ty: TypeId, items: Vec<Value>) -> Value {
+    // typed queues give typed vectors, even when empty
+    match (ty, items.len()) {
+        (TypeId::Int, 0) => Value::VecInt(vec![]),
+        (TypeId::Float, 0) => Value::VecFloat(vec![]),
+        _ => Value::pack(items),
+    }
+}
+
+fn pack<'a>(ty: TypeId, v: &Value, arena: &'a Arena<'a>) -> Value {
+    if ty.serialised() {
+        let mut out = vec![];
+        wire::value(v, Some(arena), &mut out);
+        Value::VecByte(out)
+    } else {
+        v.clone()
+    }
+}
+
+fn unpack<'a>(ty: TypeId, v: &Value, arena: &'a Arena<'a>) -> Value {
+    match v {
+        &Value::VecByte(ref b) if ty.serialised() => {
+            wire::read_value(&mut Reader::new(b), Some(arena)).expect("Malformed queue slot")
+        }
+        x => x.clone(),
+    }
 }
@@ -1,13 +1,19 @@
 
 use queues::publisher::Subscriber;
+use commands::ast::Value;
+use intercore::bus::TypeId;
+use intercore::wire::{self, Reader, put_u8, put_u16, put_u64, put_str};
 
 #[derive(PartialEq, Debug,Clone)]
 pub struct Pub {
     pub from: usize,
     pub to: usize,
     pub task_id: usize,
+    pub corr: usize,
+    pub core: usize, // where the queue lives, a named one goes by the registrar first
     pub name: String,
     pub cap: usize,
+    pub ty: TypeId,
 }
 
 #[derive(PartialEq, Debug,Clone)]
@@ -15,6 +21,7 @@ pub struct Sub {
     pub from: usize,
     pub to: usize,
     pub task_id: usize,
+    pub corr: usize,
     pub pub_id: usize,
 }
 
@@ -22,16 +29,301 @@ pub struct Sub {
 pub struct Spawn {
     pub from: usize,
     pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub prio: u8,
+    pub pin: bool, // started where it was sent, never stolen
+    pub txt: String,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Join {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub pid: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Call {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub txt: String,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Register {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub owner: usize, // core of the task that asked for the publisher
+    pub name: String,
+    pub pub_id: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Lookup {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub name: String,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Names {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Unregister {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct After {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub ms: usize,
+    pub every: bool, // re-armed after each run
+    pub txt: String,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Cancel {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub id: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct QoS {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub pid: usize,
+    pub prio: u8,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckQoS {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub prio: u8, // the one it had before
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Kill {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub pid: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckKill {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Tasks {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckTasks {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub tasks: Vec<(usize, String, u8)>, // pid, state, priority
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub enum Watcher {
+    Link(usize, usize), // core, pid that dies with the watched task
+    Monitor(usize, usize), // core, publisher told about the exit
+    Sup(usize, usize, usize), // core, supervisor, child
+}
+
+impl Watcher {
+    pub fn core(&self) -> usize {
+        match *self {
+            Watcher::Link(c, _) |
+            Watcher::Monitor(c, _) |
+            Watcher::Sup(c, _, _) => c,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Watch {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub pid: usize,
+    pub by: Watcher,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckWatch {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub pid: usize, // the watched task
+    pub link: bool, // the caller watches it back
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Down {
+    pub from: usize,
+    pub to: usize,
+    pub pid: usize,
+    pub crashed: bool,
+    pub by: Watcher,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Supervise {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub strategy: u8,
+    pub intensity: usize,
+    pub ms: usize, // the period restarts are counted over
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Child {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub sup: usize,
+    pub core: usize,
+    pub prio: u8,
     pub txt: String,
 }
 
+#[derive(PartialEq, Debug,Clone)]
+pub struct Start {
+    pub from: usize,
+    pub to: usize,
+    pub sup: usize,
+    pub child: usize,
+    pub gen: usize,
+    pub prio: u8,
+    pub txt: String,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Started {
+    pub from: usize,
+    pub to: usize,
+    pub sup: usize,
+    pub child: usize,
+    pub gen: usize,
+    pub pid: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Exit {
+    pub from: usize,
+    pub to: usize,
+    pub pid: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Steal {
+    pub from: usize,
+    pub to: usize,
+    pub load: usize, // of the idle core asking
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Load {
+    pub from: usize,
+    pub to: usize,
+    pub load: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Metrics {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Halt {
+    pub from: usize, // the core that stops first, it tells the others
+    pub to: usize,
+    pub code: u8, // the process exit code
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckMetrics {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub stats: Vec<(String, u64)>, // counter, value
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckNames {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub names: Vec<(String, usize, usize)>, // name, core, pub_id
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct Nack {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub error: String,
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct AckSub {
     pub from: usize,
     pub to: usize,
     pub task_id: usize,
+    pub corr: usize,
     pub result_id: usize,
-    pub s: Subscriber<i64>,
+    pub ty: TypeId,
+    pub s: Option<Subscriber<Value>>, // a shared memory cursor, None once it went through bytes
 }
 
 #[derive(PartialEq, Debug,Clone)]
@@ -39,6 +331,7 @@ pub struct AckPub {
     pub from: usize,
     pub to: usize,
     pub task_id: usize,
+    pub corr: usize,
     pub result_id: usize,
 }
 
@@ -47,6 +340,26 @@ pub struct AckSpawn {
     pub from: usize,
     pub to: usize,
     pub task_id: usize,
+    pub corr: usize,
+    pub pid: usize,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckJoin {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub value: Value,
+}
+
+#[derive(PartialEq, Debug,Clone)]
+pub struct AckCall {
+    pub from: usize,
+    pub to: usize,
+    pub task_id: usize,
+    pub corr: usize,
+    pub value: Value,
 }
 
 #[derive(PartialEq, Debug,Clone)]
@@ -55,13 +368,42 @@ pub enum Message {
     Sub(Sub),
     Print(String),
     Spawn(Spawn),
+    Join(Join),
+    Call(Call),
+    Register(Register),
+    Lookup(Lookup),
+    Names(Names),
+    Unregister(Unregister),
+    After(After),
+    Cancel(Cancel),
+    Kill(Kill),
+    Tasks(Tasks),
+    Watch(Watch),
+    Down(Down),
+    Supervise(Supervise),
+    Child(Child),
+    Start(Start),
+    Started(Started),
+    Exit(Exit),
+    Steal(Steal),
+    Load(Load),
+    Metrics(Metrics),
     AckSub(AckSub),
     AckPub(AckPub),
     AckSpawn(AckSpawn),
+    AckJoin(AckJoin),
+    AckCall(AckCall),
+    AckNames(AckNames),
+    AckQoS(AckQoS),
+    AckKill(AckKill),
+    AckTasks(AckTasks),
+    AckWatch(AckWatch),
+    AckMetrics(AckMetrics),
+    Nack(Nack),
     Exec(usize, String),
     Select(String, u16),
-    QoS(u8, u8, u8),
-    Halt,
+    QoS(QoS),
+    Halt(Halt),
     Nop,
 }
 
@@ -71,9 +413,722 @@ impl Default for Message {
     }
 }
 
+fn put_ids(out: &mut Vec<u8>, ids: &[usize]) {
+    for &x in ids {
+        put_u64(out, x as u64)
+    }
+}
+
+fn id(r: &mut Reader) -> Option<usize> {
+    r.u64().map(|x| x as usize)
+}
+
+fn put_watcher(out: &mut Vec<u8>, w: &Watcher) {
+    match *w {
+        Watcher::Link(core, pid) => {
+            put_u8(out, 0);
+            put_ids(out, &[core, pid, 0])
+        }
+        Watcher::Monitor(core, pub_id) => {
+            put_u8(out, 1);
+            put_ids(out, &[core, pub_id, 0])
+        }
+        Watcher::Sup(core, sup, child) => {
+            put_u8(out, 2);
+            put_ids(out, &[core, sup, child])
+        }
+    }
+}
+
+fn watcher(r: &mut Reader) -> Option<Watcher> {
+    let kind = match r.u8() {
+        Some(k) => k,
+        None => return None,
+    };
+    match (kind, id(r), id(r), id(r)) {
+        (0, Some(core), Some(pid), Some(_)) => Some(Watcher::Link(core, pid)),
+        (1, Some(core), Some(pub_id), Some(_)) => Some(Watcher::Monitor(core, pub_id)),
+        (2, Some(core), Some(sup), Some(child)) => Some(Watcher::Sup(core, sup, child)),
+        _ => None,
+    }
+}
+
+macro_rules! read {
+    ($e:expr) => {
+        match $e {
+            Some(x) => x,
+            None => return None,
+        }
+    };
+}
+
 impl Message {
-    pub fn from_u8(b: &[u8]) -> Self {
-        //
-        Message::Nop
+    pub fn to(&self) -> Option<usize> {
+        // the addressee, cores that relay the bus forward on it
+        match *self {
+            Message::Pub(ref m) => Some(m.to),
+            Message::Sub(ref m) => Some(m.to),
+            Message::Spawn(ref m) => Some(m.to),
+            Message::Join(ref m) => Some(m.to),
+            Message::Call(ref m) => Some(m.to),
+            Message::Register(ref m) => Some(m.to),
+            Message::Lookup(ref m) => Some(m.to),
+            Message::Names(ref m) => Some(m.to),
+            Message::Unregister(ref m) => Some(m.to),
+            Message::After(ref m) => Some(m.to),
+            Message::Cancel(ref m) => Some(m.to),
+            Message::QoS(ref m) => Some(m.to),
+            Message::Kill(ref m) => Some(m.to),
+            Message::Tasks(ref m) => Some(m.to),
+            Message::Watch(ref m) => Some(m.to),
+            Message::Down(ref m) => Some(m.to),
+            Message::Supervise(ref m) => Some(m.to),
+            Message::Child(ref m) => Some(m.to),
+            Message::Start(ref m) => Some(m.to),
+            Message::Started(ref m) => Some(m.to),
+            Message::Exit(ref m) => Some(m.to),
+            Message::Steal(ref m) => Some(m.to),
+            Message::Load(ref m) => Some(m.to),
+            Message::Metrics(ref m) => Some(m.to),
+            Message::Halt(ref m) => Some(m.to),
+            Message::AckSub(ref m) => Some(m.to),
+            Message::AckPub(ref m) => Some(m.to),
+            Message::AckSpawn(ref m) => Some(m.to),
+            Message::AckJoin(ref m) => Some(m.to),
+            Message::AckCall(ref m) => Some(m.to),
+            Message::AckNames(ref m) => Some(m.to),
+            Message::AckQoS(ref m) => Some(m.to),
+            Message::AckKill(ref m) => Some(m.to),
+            Message::AckTasks(ref m) => Some(m.to),
+            Message::AckWatch(ref m) => Some(m.to),
+            Message::AckMetrics(ref m) => Some(m.to),
+            Message::Nack(ref m) => Some(m.to),
+            _ => None,
+        }
+    }
+
+    pub fn requester(&self) -> Option<(usize, usize, usize)> {
+        // (core, task, corr) waiting for the answer to a request
+        match *self {
+            Message::Pub(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Sub(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Spawn(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Join(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Call(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Register(ref m) => Some((m.owner, m.task_id, m.corr)),
+            Message::Lookup(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Names(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::After(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Cancel(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::QoS(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Kill(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Tasks(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Watch(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Supervise(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Child(ref m) => Some((m.from, m.task_id, m.corr)),
+            Message::Metrics(ref m) => Some((m.from, m.task_id, m.corr)),
+            _ => None,
+        }
+    }
+
+    pub fn reply(&self) -> Option<(usize, usize, usize)> {
+        // (core, task, corr) an answer is meant for
+        match *self {
+            Message::AckSub(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckPub(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckSpawn(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckJoin(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckCall(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckNames(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckQoS(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckKill(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckTasks(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckWatch(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::AckMetrics(ref m) => Some((m.to, m.task_id, m.corr)),
+            Message::Nack(ref m) => Some((m.to, m.task_id, m.corr)),
+            _ => None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // one tag byte, then the fields in declaration order
+        let mut out = vec![];
+        match *self {
+            Message::Nop => put_u8(&mut out, 0),
+            Message::Pub(ref p) => {
+                put_u8(&mut out, 1);
+                put_ids(&mut out, &[p.from, p.to, p.task_id, p.corr, p.core]);
+                put_str(&mut out, &p.name);
+                put_u64(&mut out, p.cap as u64);
+                put_u8(&mut out, p.ty as u8)
+            }
+            Message::Sub(ref s) => {
+                put_u8(&mut out, 2);
+                put_ids(&mut out, &[s.from, s.to, s.task_id, s.corr, s.pub_id])
+            }
+            Message::Print(ref s) => {
+                put_u8(&mut out, 3);
+                put_str(&mut out, s)
+            }
+            Message::Spawn(ref s) => {
+                put_u8(&mut out, 4);
+                put_ids(&mut out, &[s.from, s.to, s.task_id, s.corr]);
+                put_u8(&mut out, s.prio);
+                put_u8(&mut out, s.pin as u8);
+                put_str(&mut out, &s.txt)
+            }
+            Message::Join(ref j) => {
+                put_u8(&mut out, 5);
+                put_ids(&mut out, &[j.from, j.to, j.task_id, j.corr, j.pid])
+            }
+            Message::Call(ref c) => {
+                put_u8(&mut out, 6);
+                put_ids(&mut out, &[c.from, c.to, c.task_id, c.corr]);
+                put_str(&mut out, &c.txt)
+            }
+            Message::AckSub(ref a) => {
+                // the subscriber is a shared memory cursor, it can't leave the process
+                put_u8(&mut out, 7);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.result_id]);
+                put_u8(&mut out, a.ty as u8)
+            }
+            Message::AckPub(ref a) => {
+                put_u8(&mut out, 8);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.result_id])
+            }
+            Message::AckSpawn(ref a) => {
+                put_u8(&mut out, 9);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.pid])
+            }
+            Message::AckJoin(ref a) => {
+                put_u8(&mut out, 10);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr]);
+                wire::value(&a.value, None, &mut out)
+            }
+            Message::AckCall(ref a) => {
+                put_u8(&mut out, 11);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr]);
+                wire::value(&a.value, None, &mut out)
+            }
+            Message::Exec(task, ref cmd) => {
+                put_u8(&mut out, 12);
+                put_ids(&mut out, &[task]);
+                put_str(&mut out, cmd)
+            }
+            Message::Select(ref s, x) => {
+                put_u8(&mut out, 13);
+                put_str(&mut out, s);
+                put_u16(&mut out, x)
+            }
+            Message::QoS(ref q) => {
+                put_u8(&mut out, 14);
+                put_ids(&mut out, &[q.from, q.to, q.task_id, q.corr, q.pid]);
+                put_u8(&mut out, q.prio)
+            }
+            Message::Halt(ref h) => {
+                put_u8(&mut out, 15);
+                put_ids(&mut out, &[h.from, h.to]);
+                put_u8(&mut out, h.code)
+            }
+            Message::Register(ref g) => {
+                put_u8(&mut out, 16);
+                put_ids(&mut out, &[g.from, g.to, g.task_id, g.corr, g.owner]);
+                put_str(&mut out, &g.name);
+                put_ids(&mut out, &[g.pub_id])
+            }
+            Message::Lookup(ref l) => {
+                put_u8(&mut out, 17);
+                put_ids(&mut out, &[l.from, l.to, l.task_id, l.corr]);
+                put_str(&mut out, &l.name)
+            }
+            Message::Names(ref n) => {
+                put_u8(&mut out, 18);
+                put_ids(&mut out, &[n.from, n.to, n.task_id, n.corr])
+            }
+            Message::Unregister(ref u) => {
+                put_u8(&mut out, 19);
+                put_ids(&mut out, &[u.from, u.to, u.task_id])
+            }
+            Message::AckNames(ref a) => {
+                put_u8(&mut out, 20);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.names.len()]);
+                for &(ref name, core, pub_id) in &a.names {
+                    put_str(&mut out, name);
+                    put_ids(&mut out, &[core, pub_id])
+                }
+            }
+            Message::Nack(ref n) => {
+                put_u8(&mut out, 21);
+                put_ids(&mut out, &[n.from, n.to, n.task_id, n.corr]);
+                put_str(&mut out, &n.error)
+            }
+            Message::After(ref a) => {
+                put_u8(&mut out, 22);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.ms]);
+                put_u8(&mut out, a.every as u8);
+                put_str(&mut out, &a.txt)
+            }
+            Message::Cancel(ref c) => {
+                put_u8(&mut out, 23);
+                put_ids(&mut out, &[c.from, c.to, c.task_id, c.corr, c.id])
+            }
+            Message::AckQoS(ref a) => {
+                put_u8(&mut out, 24);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr]);
+                put_u8(&mut out, a.prio)
+            }
+            Message::Kill(ref k) => {
+                put_u8(&mut out, 25);
+                put_ids(&mut out, &[k.from, k.to, k.task_id, k.corr, k.pid])
+            }
+            Message::AckKill(ref a) => {
+                put_u8(&mut out, 26);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr])
+            }
+            Message::Tasks(ref t) => {
+                put_u8(&mut out, 27);
+                put_ids(&mut out, &[t.from, t.to, t.task_id, t.corr])
+            }
+            Message::AckTasks(ref a) => {
+                put_u8(&mut out, 28);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.tasks.len()]);
+                for &(pid, ref state, prio) in &a.tasks {
+                    put_ids(&mut out, &[pid]);
+                    put_str(&mut out, state);
+                    put_u8(&mut out, prio)
+                }
+            }
+            Message::Watch(ref w) => {
+                put_u8(&mut out, 29);
+                put_ids(&mut out, &[w.from, w.to, w.task_id, w.corr, w.pid]);
+                put_watcher(&mut out, &w.by)
+            }
+            Message::AckWatch(ref a) => {
+                put_u8(&mut out, 30);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.pid]);
+                put_u8(&mut out, a.link as u8)
+            }
+            Message::Down(ref d) => {
+                put_u8(&mut out, 31);
+                put_ids(&mut out, &[d.from, d.to, d.pid]);
+                put_u8(&mut out, d.crashed as u8);
+                put_watcher(&mut out, &d.by)
+            }
+            Message::Supervise(ref s) => {
+                put_u8(&mut out, 32);
+                put_ids(&mut out, &[s.from, s.to, s.task_id, s.corr]);
+                put_u8(&mut out, s.strategy);
+                put_ids(&mut out, &[s.intensity, s.ms])
+            }
+            Message::Child(ref c) => {
+                put_u8(&mut out, 33);
+                put_ids(&mut out, &[c.from, c.to, c.task_id, c.corr, c.sup, c.core]);
+                put_u8(&mut out, c.prio);
+                put_str(&mut out, &c.txt)
+            }
+            Message::Start(ref s) => {
+                put_u8(&mut out, 34);
+                put_ids(&mut out, &[s.from, s.to, s.sup, s.child, s.gen]);
+                put_u8(&mut out, s.prio);
+                put_str(&mut out, &s.txt)
+            }
+            Message::Started(ref s) => {
+                put_u8(&mut out, 35);
+                put_ids(&mut out, &[s.from, s.to, s.sup, s.child, s.gen, s.pid])
+            }
+            Message::Exit(ref e) => {
+                put_u8(&mut out, 36);
+                put_ids(&mut out, &[e.from, e.to, e.pid])
+            }
+            Message::Steal(ref s) => {
+                put_u8(&mut out, 37);
+                put_ids(&mut out, &[s.from, s.to, s.load])
+            }
+            Message::Load(ref l) => {
+                put_u8(&mut out, 38);
+                put_ids(&mut out, &[l.from, l.to, l.load])
+            }
+            Message::Metrics(ref m) => {
+                put_u8(&mut out, 39);
+                put_ids(&mut out, &[m.from, m.to, m.task_id, m.corr])
+            }
+            Message::AckMetrics(ref a) => {
+                put_u8(&mut out, 40);
+                put_ids(&mut out, &[a.from, a.to, a.task_id, a.corr, a.stats.len()]);
+                for &(ref name, v) in &a.stats {
+                    put_str(&mut out, name);
+                    put_u64(&mut out, v)
+                }
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Option<Self> {
+        let ref mut r = Reader::new(b);
+        let m = match read!(r.u8()) {
+            0 => Message::Nop,
+            1 => {
+                Message::Pub(Pub {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    core: read!(id(r)),
+                    name: read!(r.str()),
+                    cap: read!(id(r)),
+                    ty: read!(r.u8().and_then(TypeId::from_u8)),
+                })
+            }
+            2 => {
+                Message::Sub(Sub {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pub_id: read!(id(r)),
+                })
+            }
+            3 => Message::Print(read!(r.str())),
+            4 => {
+                Message::Spawn(Spawn {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    prio: read!(r.u8()),
+                    pin: read!(r.u8()) != 0,
+                    txt: read!(r.str()),
+                })
+            }
+            5 => {
+                Message::Join(Join {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pid: read!(id(r)),
+                })
+            }
+            6 => {
+                Message::Call(Call {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    txt: read!(r.str()),
+                })
+            }
+            7 => {
+                Message::AckSub(AckSub {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    result_id: read!(id(r)),
+                    ty: read!(r.u8().and_then(TypeId::from_u8)),
+                    s: None,
+                })
+            }
+            8 => {
+                Message::AckPub(AckPub {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    result_id: read!(id(r)),
+                })
+            }
+            9 => {
+                Message::AckSpawn(AckSpawn {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pid: read!(id(r)),
+                })
+            }
+            10 => {
+                Message::AckJoin(AckJoin {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    value: read!(wire::read_value(r, None)),
+                })
+            }
+            11 => {
+                Message::AckCall(AckCall {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    value: read!(wire::read_value(r, None)),
+                })
+            }
+            12 => Message::Exec(read!(id(r)), read!(r.str())),
+            13 => Message::Select(read!(r.str()), read!(r.u16())),
+            14 => {
+                Message::QoS(QoS {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pid: read!(id(r)),
+                    prio: read!(r.u8()),
+                })
+            }
+            15 => {
+                Message::Halt(Halt {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    code: read!(r.u8()),
+                })
+            }
+            16 => {
+                Message::Register(Register {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    owner: read!(id(r)),
+                    name: read!(r.str()),
+                    pub_id: read!(id(r)),
+                })
+            }
+            17 => {
+                Message::Lookup(Lookup {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    name: read!(r.str()),
+                })
+            }
+            18 => {
+                Message::Names(Names {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                })
+            }
+            19 => {
+                Message::Unregister(Unregister {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                })
+            }
+            20 => {
+                let (from, to, task_id, corr, n) = (read!(id(r)), read!(id(r)), read!(id(r)), read!(id(r)), read!(id(r)));
+                let mut names = vec![];
+                for _ in 0..n {
+                    names.push((read!(r.str()), read!(id(r)), read!(id(r))));
+                }
+                Message::AckNames(AckNames {
+                    from: from,
+                    to: to,
+                    task_id: task_id,
+                    corr: corr,
+                    names: names,
+                })
+            }
+            21 => {
+                Message::Nack(Nack {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    error: read!(r.str()),
+                })
+            }
+            22 => {
+                Message::After(After {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    ms: read!(id(r)),
+                    every: read!(r.u8()) != 0,
+                    txt: read!(r.str()),
+                })
+            }
+            23 => {
+                Message::Cancel(Cancel {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    id: read!(id(r)),
+                })
+            }
+            24 => {
+                Message::AckQoS(AckQoS {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    prio: read!(r.u8()),
+                })
+            }
+            25 => {
+                Message::Kill(Kill {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pid: read!(id(r)),
+                })
+            }
+            26 => {
+                Message::AckKill(AckKill {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                })
+            }
+            27 => {
+                Message::Tasks(Tasks {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                })
+            }
+            28 => {
+                let (from, to, task_id, corr, n) = (read!(id(r)), read!(id(r)), read!(id(r)), read!(id(r)), read!(id(r)));
+                let mut tasks = vec![];
+                for _ in 0..n {
+                    tasks.push((read!(id(r)), read!(r.str()), read!(r.u8())));
+                }
+                Message::AckTasks(AckTasks {
+                    from: from,
+                    to: to,
+                    task_id: task_id,
+                    corr: corr,
+                    tasks: tasks,
+                })
+            }
+            29 => {
+                Message::Watch(Watch {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pid: read!(id(r)),
+                    by: read!(watcher(r)),
+                })
+            }
+            30 => {
+                Message::AckWatch(AckWatch {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    pid: read!(id(r)),
+                    link: read!(r.u8()) != 0,
+                })
+            }
+            31 => {
+                Message::Down(Down {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    pid: read!(id(r)),
+                    crashed: read!(r.u8()) != 0,
+                    by: read!(watcher(r)),
+                })
+            }
+            32 => {
+                Message::Supervise(Supervise {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    strategy: read!(r.u8()),
+                    intensity: read!(id(r)),
+                    ms: read!(id(r)),
+                })
+            }
+            33 => {
+                Message::Child(Child {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                    sup: read!(id(r)),
+                    core: read!(id(r)),
+                    prio: read!(r.u8()),
+                    txt: read!(r.str()),
+                })
+            }
+            34 => {
+                Message::Start(Start {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    sup: read!(id(r)),
+                    child: read!(id(r)),
+                    gen: read!(id(r)),
+                    prio: read!(r.u8()),
+                    txt: read!(r.str()),
+                })
+            }
+            35 => {
+                Message::Started(Started {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    sup: read!(id(r)),
+                    child: read!(id(r)),
+                    gen: read!(id(r)),
+                    pid: read!(id(r)),
+                })
+            }
+            36 => {
+                Message::Exit(Exit {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    pid: read!(id(r)),
+                })
+            }
+            37 => {
+                Message::Steal(Steal {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    load: read!(id(r)),
+                })
+            }
+            38 => {
+                Message::Load(Load {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    load: read!(id(r)),
+                })
+            }
+            39 => {
+                Message::Metrics(Metrics {
+                    from: read!(id(r)),
+                    to: read!(id(r)),
+                    task_id: read!(id(r)),
+                    corr: read!(id(r)),
+                })
+            }
+            40 => {
+                let (from, to, task_id, corr, n) = (read!(id(r)), read!(id(r)), read!(id(r)), read!(id(r)), read!(id(r)));
+                let mut stats = vec![];
+                for _ in 0..n {
+                    stats.push((read!(r.str()), read!(r.u64())));
+                }
+                Message::AckMetrics(AckMetrics {
+                    from: from,
+                    to: to,
+                    task_id: task_id,
+                    corr: corr,
+                    stats: stats,
+                })
+            }
+            _ => return None,
+        };
+        if r.done() { Some(m) } else { None }
     }
 }
@@ -3,3 +3,6 @@ pub mod bus;
 pub mod message;
 pub mod client;
 pub mod server;
+pub mod wire;
+pub mod topology;
+pub mod pending;
@@ -0,0 +1,82 @@
+// Requests of local tasks waiting for an answer. A task has at most one
+// request in flight, known by its correlation id, so answers carrying any
+// other id are late or duplicated and get dropped. Control requests expire
+// on the timer wheel, joins and calls wait for user code and have no deadline.
+
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::time::Duration;
+use timers::timer::{Timer, Timeout, Builder};
+use intercore::message::Message;
+
+pub const TIMEOUT: u64 = 5000; // ms
+
+pub struct Pending {
+    pub timeout: Duration,
+    table: UnsafeCell<HashMap<usize, (usize, Option<Timeout>)>>,
+    timer: UnsafeCell<Timer<(usize, usize)>>,
+}
+
+impl Pending {
+    pub fn new(timeout: Duration) -> Self {
+        Pending {
+            timeout: timeout,
+            table: UnsafeCell::new(HashMap::new()),
+            timer: UnsafeCell::new(Builder::default().tick_duration(Duration::from_millis(10)).build()),
+        }
+    }
+
+    pub fn expect(&self, m: &Message) {
+        let table = unsafe { &mut *self.table.get() };
+        let timer = unsafe { &mut *self.timer.get() };
+        if let Some((_, task, corr)) = m.requester() {
+            let t = match *m {
+                Message::Join(_) | Message::Call(_) => None,
+                _ => timer.set_timeout(self.timeout, (task, corr)).ok(),
+            };
+            if let Some((_, Some(old))) = table.insert(task, (corr, t)) {
+                timer.cancel_timeout(&old);
+            }
+        }
+    }
+
+    pub fn answer(&self, task: usize, corr: usize) -> bool {
+        let table = unsafe { &mut *self.table.get() };
+        let timer = unsafe { &mut *self.timer.get() };
+        match table.get(&task) {
+            Some(&(c, _)) if c == corr => (),
+            _ => return false,
+        }
+        if let Some((_, Some(t))) = table.remove(&task) {
+            timer.cancel_timeout(&t);
+        }
+        true
+    }
+
+    pub fn forget(&self, task: usize) {
+        // the task is gone, whatever answers it is dropped
+        let table = unsafe { &mut *self.table.get() };
+        let timer = unsafe { &mut *self.timer.get() };
+        if let Some((_, Some(t))) = table.remove(&task) {
+            timer.cancel_timeout(&t);
+        }
+    }
+
+    pub fn expired(&self) -> Vec<(usize, usize)> {
+        let table = unsafe { &mut *self.table.get() };
+        let timer = unsafe { &mut *self.timer.get() };
+        let mut v = vec![];
+        while let Some((task, corr)) = timer.poll() {
+            match table.get(&task) {
+                Some(&(c, _)) if c == corr => v.push((task, corr)),
+                _ => continue,
+            }
+            table.remove(&task);
+        }
+        v
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { &*self.table.get() }.len()
+    }
+}
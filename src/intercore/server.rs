@@ -1,98 +1,558 @@
 
 use queues::publisher::Publisher;
 use intercore::bus::{Channel, send};
-use intercore::message::{Message, AckPub, AckSub};
+use intercore::message::{Message, AckPub, AckSub, AckSpawn, AckJoin, AckNames, AckQoS, AckKill, AckTasks, AckWatch,
+                         AckMetrics, Started, Watcher, Down, Register, Pub, Sub, Nack};
 use reactors::cps::CpsTask;
 use reactors::job::Job;
 use reactors::task::{Task, Context, Termination, Poll, Error};
-use reactors::scheduler::Scheduler;
+use reactors::scheduler::{Scheduler, DUMP};
+use reactors::sup::{Strategy, NOT_STARTED};
+use std::time::{Duration, Instant};
 use handle::{from_raw, into_raw, use_};
 
+// Core 0 keeps the names of publishers for all cores
+
+pub const REGISTRAR: usize = 0;
+
+#[derive(Debug, Clone)]
+pub struct Registered {
+    pub core: usize,
+    pub pub_id: usize,
+    pub owner: usize,
+    pub task_id: usize,
+}
+
+// A name is held with a PENDING publisher from the registrar's check until its queue exists
+const PENDING: usize = !0;
+
+fn register(sched: &mut Scheduler, name: &str, r: Registered) -> Result<(), String> {
+    if let Some(x) = sched.registry.get(name) {
+        if x.pub_id != PENDING || x.owner != r.owner || x.task_id != r.task_id {
+            return Err(format!("{}: name is taken on core {}", name, x.core));
+        }
+    }
+    sched.registry.insert(name.to_string(), r);
+    Ok(())
+}
+
+fn subscribe(sched: &Scheduler, pub_id: usize) -> Option<usize> {
+    match sched.queues.publishers().get_mut(pub_id) {
+        Some(p) => {
+            let ty = sched.queues.publisher_type(pub_id).expect("untyped publisher");
+            Some(sched.queues.add_subscriber(p.subscribe(), ty))
+        }
+        None => None,
+    }
+}
+
+fn refuse<'a>(bus: &'a Channel, to: usize, task_id: usize, corr: usize, error: String) -> Context<'a> {
+    if to == bus.id {
+        return Context::Fail(task_id, error);
+    }
+    send(bus,
+         Message::Nack(Nack {
+             from: bus.id,
+             to: to,
+             task_id: task_id,
+             corr: corr,
+             error: error,
+         }));
+    Context::Nil
+}
+
 // The InterCore Delivery by Adressee
 
 pub fn delivery<'a>(context: Context<'a>, sched: &'a mut Scheduler<'a>) -> Poll<Context<'a>, Error> {
     match context {
-        Context::NodeAck(task, res) => {
-            use_(sched).tasks.get_mut(task).expect("no shell").0.poll(context, sched)
+        Context::NodeAck(task, _) |
+        Context::Cont(task, _) |
+        Context::Fail(task, _) => {
+            match use_(sched).task(task) {
+                Some(t) => {
+                    let r = t.0.poll(context, use_(sched));
+                    sched.settle(task, &r);
+                    r
+                }
+                None => Poll::Yield(Context::Nil), // reaped meanwhile
+            }
         }
         Context::Node(..) => Poll::End(context),
         _ => Poll::Yield(Context::Nil)
     }
 }
 
-// InterCore State Machine
+// Answers are matched against the request their task waits for
 
 pub fn intercore<'a>(sched: &'a mut Scheduler<'a>, message: Option<&'a Message>, bus: &'a Channel) -> Context<'a> {
+    if let Some((core, task, corr)) = message.and_then(|m| m.reply()) {
+        if core == bus.id && task != DUMP && !sched.pending.answer(task, corr) {
+            return Context::Nil; // late or duplicated
+        }
+    }
+    let local = match message.and_then(|m| m.requester()) {
+        Some((core, task, corr)) if core == bus.id => Some((task, corr)),
+        _ => None,
+    };
+    let x = into_raw(sched);
+    match dispatch(from_raw(x), message, bus) {
+        c @ Context::NodeAck(..) |
+        c @ Context::Fail(..) => {
+            // answered in place
+            match local {
+                Some((task, corr)) if !from_raw(x).pending.answer(task, corr) => Context::Nil,
+                _ => c,
+            }
+        }
+        c => c,
+    }
+}
+
+// InterCore State Machine
+
+fn dispatch<'a>(sched: &'a mut Scheduler<'a>, message: Option<&'a Message>, bus: &'a Channel) -> Context<'a> {
 
     match message {
 
         Some(&Message::Spawn(ref v)) if v.to == bus.id => {
             println!("InterCore Spawn {:?} {:?}", bus.id, v);
+            if sched.hold(v) {
+                return Context::Nil; // acked by the core that starts it
+            }
             let x = into_raw(sched);
-            from_raw(x).spawn(Job::Cps(CpsTask::new(sched.mem())),
-                              Termination::Recursive,
-                              Some(&v.txt));
+            let t = match from_raw(x).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                            Termination::Recursive,
+                                            Some(&v.txt)) {
+                Some(t) => t,
+                None => return refuse(bus, v.from, v.task_id, v.corr, "too many tasks".to_string()),
+            };
+            from_raw(x).prioritize(t.0, v.prio);
+            send(bus,
+                 Message::AckSpawn(AckSpawn {
+                     from: bus.id,
+                     to: v.from,
+                     task_id: v.task_id,
+                     corr: v.corr,
+                     pid: t.0,
+                 }));
+            Context::Nil
+        }
+
+        Some(&Message::AckSpawn(ref a)) if a.to == bus.id => {
+            println!("InterCore AckSpawn {:?} {:?}", bus.id, a);
+            Context::Cont(a.task_id, message.unwrap())
+        }
+
+        Some(&Message::Join(ref j)) if j.to == bus.id => {
+            println!("InterCore Join {:?} {:?}", bus.id, j);
+            if !sched.results.contains_key(&j.pid) && sched.slot(j.pid).is_none() {
+                return refuse(bus, j.from, j.task_id, j.corr, format!("{}: no such task", j.pid));
+            }
+            match sched.results.get(&j.pid).cloned() {
+                Some(v) => {
+                    send(bus,
+                         Message::AckJoin(AckJoin {
+                             from: bus.id,
+                             to: j.from,
+                             task_id: j.task_id,
+                             corr: j.corr,
+                             value: v,
+                         }))
+                }
+                None => sched.joins.push(j.clone()), // answered when the task ends
+            }
             Context::Nil
         }
 
-        Some(&Message::QoS(task, bus, io)) => {
-            println!("InterCore QoS {:?} {:?} {:?}", task, bus, io);
+        Some(&Message::AckJoin(ref a)) if a.to == bus.id => {
+            println!("InterCore AckJoin {:?} {:?}", bus.id, a);
+            Context::Cont(a.task_id, message.unwrap())
+        }
+
+        Some(&Message::Call(ref c)) if c.to == bus.id => {
+            println!("InterCore Call {:?} {:?}", bus.id, c);
+            let x = into_raw(sched);
+            let t = match from_raw(x).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                            Termination::Recursive,
+                                            Some(&c.txt)) {
+                Some(t) => t,
+                None => return refuse(bus, c.from, c.task_id, c.corr, "too many tasks".to_string()),
+            };
+            from_raw(x).calls.push((t.0, c.clone())); // replied when the task ends
             Context::Nil
         }
 
-        Some(&Message::Exec(ref task, ref cmd)) if 0 == bus.id => {
-            let mut t = into_raw(sched.tasks.get_mut(task.clone()).expect("no shell"));
+        Some(&Message::AckCall(ref a)) if a.to == bus.id => {
+            println!("InterCore AckCall {:?} {:?}", bus.id, a);
+            Context::Cont(a.task_id, message.unwrap())
+        }
+
+        Some(&Message::QoS(ref q)) if q.to == bus.id => {
+            println!("InterCore QoS {:?} {:?}", bus.id, q);
+            match sched.prioritize(q.pid, q.prio) {
+                None => refuse(bus, q.from, q.task_id, q.corr, format!("{}: no such task", q.pid)),
+                Some(old) if q.from == bus.id => Context::NodeAck(q.task_id, old as usize),
+                Some(old) => {
+                    send(bus,
+                         Message::AckQoS(AckQoS {
+                             from: bus.id,
+                             to: q.from,
+                             task_id: q.task_id,
+                             corr: q.corr,
+                             prio: old,
+                         }));
+                    Context::Nil
+                }
+            }
+        }
+
+        Some(&Message::AckQoS(ref a)) if a.to == bus.id => Context::NodeAck(a.task_id, a.prio as usize),
+
+        Some(&Message::Exec(ref task, ref cmd)) if sched.bsp == bus.id => {
+            let mut t = into_raw(sched.task(*task).expect("no shell"));
             from_raw(t).0.exec(Some(cmd));
             let x = from_raw(t).0.poll(Context::Nil, use_(sched));
             println!("InterCore Exec {:?} {:?} {:?}", task, cmd, x);
+            sched.settle(*task, &x);
             Context::Nil
         }
 
-        Some(&Message::Pub(ref p)) if p.to == p.from && p.to == bus.id => {
-            println!("Local Pub {:?} {:?}", bus.id, p);
-            sched.queues.publishers().push(Publisher::with_capacity(p.cap));
-            let mut t = use_(sched).tasks.get_mut(p.task_id).expect("no task");
-            let id = use_(sched).queues.publishers().len() - 1;
-            Context::NodeAck(p.task_id, id)
+        Some(&Message::Pub(ref p)) if p.to == bus.id => {
+            println!("InterCore Pub {:?} {:?}", bus.id, p);
+            if p.name != "" && bus.id == REGISTRAR {
+                // the name is held before any queue is made for it
+                let r = Registered {
+                    core: p.core,
+                    pub_id: PENDING,
+                    owner: p.from,
+                    task_id: p.task_id,
+                };
+                if let Err(e) = register(sched, &p.name, r) {
+                    return refuse(bus, p.from, p.task_id, p.corr, e);
+                }
+                if p.core != bus.id {
+                    send(bus, Message::Pub(Pub { to: p.core, ..p.clone() }));
+                    return Context::Nil;
+                }
+            }
+            let id = sched.queues.add_publisher(Publisher::with_capacity(p.cap), p.ty);
+            if p.name != "" {
+                if bus.id != REGISTRAR {
+                    // the registrar acks the owner once the name is taken
+                    send(bus,
+                         Message::Register(Register {
+                             from: bus.id,
+                             to: REGISTRAR,
+                             task_id: p.task_id,
+                             corr: p.corr,
+                             owner: p.from,
+                             name: p.name.clone(),
+                             pub_id: id,
+                         }));
+                    return Context::Nil;
+                }
+                if let Some(r) = sched.registry.get_mut(&p.name) {
+                    r.pub_id = id;
+                }
+            }
+            if p.from == bus.id {
+                return Context::NodeAck(p.task_id, id);
+            }
+            send(bus,
+                 Message::AckPub(AckPub {
+                     from: bus.id,
+                     to: p.from,
+                     task_id: p.task_id,
+                     corr: p.corr,
+                     result_id: id,
+                 }));
+            Context::Nil
         }
 
         Some(&Message::Sub(ref sb)) if sb.to == sb.from && sb.to == bus.id => {
             println!("Local Sub {:?} {:?}", bus.id, sb);
-            let mut sub_index = None;
-            if let Some(p) = sched.queues.publishers().get_mut(sb.pub_id as usize) {
-                let subscriber = p.subscribe();
-                {
-                    let subs = sched.queues.subscribers();
-                    subs.push(subscriber);
-                    sub_index = Some(subs.len() - 1);
+            match subscribe(sched, sb.pub_id) {
+                Some(idx) => Context::NodeAck(sb.task_id, idx),
+                None => Context::Nil,
+            }
+        }
+
+        Some(&Message::Register(ref g)) if g.to == bus.id => {
+            println!("InterCore Register {:?} {:?}", bus.id, g);
+            let r = Registered {
+                core: g.from,
+                pub_id: g.pub_id,
+                owner: g.owner,
+                task_id: g.task_id,
+            };
+            match register(sched, &g.name, r) {
+                Err(e) => refuse(bus, g.owner, g.task_id, g.corr, e),
+                Ok(()) if g.owner == bus.id => Context::NodeAck(g.task_id, g.pub_id),
+                Ok(()) => {
+                    send(bus,
+                         Message::AckPub(AckPub {
+                             from: g.from,
+                             to: g.owner,
+                             task_id: g.task_id,
+                             corr: g.corr,
+                             result_id: g.pub_id,
+                         }));
+                    Context::Nil
                 }
             }
-            if let Some(idx) = sub_index {
-                let h = into_raw(sched);
-                let mut t = from_raw(h).tasks.get_mut(sb.task_id).expect("no task");
-                return Context::NodeAck(sb.task_id, idx);
+        }
+
+        Some(&Message::Lookup(ref l)) if l.to == bus.id => {
+            println!("InterCore Lookup {:?} {:?}", bus.id, l);
+            match sched.registry.get(&l.name).map(|r| (r.core, r.pub_id)) {
+                Some((_, PENDING)) | None => refuse(bus, l.from, l.task_id, l.corr, format!("{}: no such publisher", l.name)),
+                Some((core, pub_id)) if core == bus.id && l.from == bus.id => {
+                    match subscribe(sched, pub_id) {
+                        Some(idx) => Context::NodeAck(l.task_id, idx),
+                        None => refuse(bus, l.from, l.task_id, l.corr, format!("{}: no such publisher", l.name)),
+                    }
+                }
+                Some((core, pub_id)) => {
+                    // the owning core acks the subscriber directly
+                    send(bus,
+                         Message::Sub(Sub {
+                             from: l.from,
+                             to: core,
+                             task_id: l.task_id,
+                             corr: l.corr,
+                             pub_id: pub_id,
+                         }));
+                    Context::Nil
+                }
             }
+        }
+
+        Some(&Message::Names(ref n)) if n.to == bus.id => {
+            let mut names: Vec<(String, usize, usize)> =
+                sched.registry
+                    .iter()
+                    .filter(|&(_, r)| r.pub_id != PENDING)
+                    .map(|(k, r)| (k.clone(), r.core, r.pub_id))
+                    .collect();
+            names.sort();
+            send(bus,
+                 Message::AckNames(AckNames {
+                     from: bus.id,
+                     to: n.from,
+                     task_id: n.task_id,
+                     corr: n.corr,
+                     names: names,
+                 }));
             Context::Nil
         }
 
-        Some(&Message::Pub(ref p)) if p.to == bus.id => {
-            let mut pb = Publisher::with_capacity(p.cap);
-            sched.queues.publishers().push(pb);
-            println!("InterCore Pub {:?} {:?}", bus.id, p);
+        Some(&Message::Unregister(ref u)) if u.to == bus.id => {
+            println!("InterCore Unregister {:?} {:?}", bus.id, u);
+            sched.registry.retain(|_, r| !(r.owner == u.from && r.task_id == u.task_id));
+            Context::Nil
+        }
+
+        // timers are local, the task that sets one runs it
+
+        Some(&Message::After(ref a)) if a.to == bus.id && a.from == bus.id => {
+            println!("InterCore After {:?} {:?}", bus.id, a);
+            Context::NodeAck(a.task_id, sched.after(a.task_id, a.ms, a.every, a.txt.clone()))
+        }
+
+        Some(&Message::Cancel(ref c)) if c.to == bus.id && c.from == bus.id => {
+            Context::NodeAck(c.task_id, sched.cancel(c.id) as usize)
+        }
+
+        Some(&Message::Kill(ref k)) if k.to == bus.id => {
+            println!("InterCore Kill {:?} {:?}", bus.id, k);
+            if k.from == bus.id && k.task_id == k.pid {
+                return refuse(bus, k.from, k.task_id, k.corr, "a task can't kill itself".to_string());
+            }
+            if !sched.kill(k.pid) {
+                return refuse(bus, k.from, k.task_id, k.corr, format!("{}: no such task", k.pid));
+            }
+            if k.from == bus.id {
+                return Context::NodeAck(k.task_id, 1);
+            }
             send(bus,
-                 Message::AckPub(AckPub {
+                 Message::AckKill(AckKill {
                      from: bus.id,
-                     to: p.from,
-                     task_id: p.task_id,
-                     result_id: sched.queues.publishers().len() - 1,
+                     to: k.from,
+                     task_id: k.task_id,
+                     corr: k.corr,
+                 }));
+            Context::Nil
+        }
+
+        Some(&Message::Tasks(ref t)) if t.to == bus.id => {
+            send(bus,
+                 Message::AckTasks(AckTasks {
+                     from: bus.id,
+                     to: t.from,
+                     task_id: t.task_id,
+                     corr: t.corr,
+                     tasks: sched.listing(),
+                 }));
+            Context::Nil
+        }
+
+        // links, monitors and supervisors are told by the core of the task that ends
+
+        Some(&Message::Watch(ref w)) if w.to == bus.id => {
+            println!("InterCore Watch {:?} {:?}", bus.id, w);
+            if sched.slot(w.pid).is_none() {
+                return refuse(bus, w.from, w.task_id, w.corr, format!("{}: no such task", w.pid));
+            }
+            sched.watchers.entry(w.pid).or_insert(vec![]).push(w.by.clone());
+            // a link goes both ways, the caller's core watches back when it is acked
+            let link = match w.by {
+                Watcher::Link(..) => true,
+                _ => false,
+            };
+            if w.from == bus.id {
+                if link {
+                    sched.watchers.entry(w.task_id).or_insert(vec![]).push(Watcher::Link(bus.id, w.pid));
+                }
+                return Context::NodeAck(w.task_id, 1);
+            }
+            send(bus,
+                 Message::AckWatch(AckWatch {
+                     from: bus.id,
+                     to: w.from,
+                     task_id: w.task_id,
+                     corr: w.corr,
+                     pid: w.pid,
+                     link: link,
+                 }));
+            Context::Nil
+        }
+
+        Some(&Message::Down(ref d)) if d.to == bus.id => {
+            println!("InterCore Down {:?} {:?}", bus.id, d);
+            sched.down(d);
+            Context::Nil
+        }
+
+        Some(&Message::Supervise(ref v)) if v.to == bus.id && v.from == bus.id => {
+            let strategy = match Strategy::from_u8(v.strategy) {
+                Some(x) => x,
+                None => return refuse(bus, v.from, v.task_id, v.corr, format!("{}: unknown strategy", v.strategy)),
+            };
+            let id = sched.supervise(v.task_id, strategy, v.intensity, Duration::from_millis(v.ms as u64));
+            Context::NodeAck(v.task_id, id)
+        }
+
+        Some(&Message::Child(ref c)) if c.to == bus.id && c.from == bus.id => {
+            println!("InterCore Child {:?} {:?}", bus.id, c);
+            match sched.child(c.sup, c.core, c.prio, c.txt.clone()) {
+                Some(k) => Context::NodeAck(c.task_id, k),
+                None => refuse(bus, c.from, c.task_id, c.corr, format!("{}: no such supervisor", c.sup)),
+            }
+        }
+
+        Some(&Message::Start(ref s)) if s.to == bus.id => {
+            println!("InterCore Start {:?} {:?}", bus.id, s);
+            let x = into_raw(sched);
+            let t = match from_raw(x).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                            Termination::Recursive,
+                                            Some(&s.txt)) {
+                Some(t) => t,
+                None => {
+                    // no slot here, the supervisor counts it as a crash of the child
+                    send(bus,
+                         Message::Down(Down {
+                             from: bus.id,
+                             to: s.from,
+                             pid: NOT_STARTED,
+                             crashed: true,
+                             by: Watcher::Sup(s.from, s.sup, s.child),
+                         }));
+                    return Context::Nil;
+                }
+            };
+            from_raw(x).prioritize(t.0, s.prio);
+            from_raw(x).watchers.insert(t.0, vec![Watcher::Sup(s.from, s.sup, s.child)]);
+            send(bus,
+                 Message::Started(Started {
+                     from: bus.id,
+                     to: s.from,
+                     sup: s.sup,
+                     child: s.child,
+                     gen: s.gen,
+                     pid: t.0,
+                 }));
+            Context::Nil
+        }
+
+        Some(&Message::Started(ref s)) if s.to == bus.id => {
+            sched.started(s);
+            Context::Nil
+        }
+
+        Some(&Message::Exit(ref e)) if e.to == bus.id => {
+            println!("InterCore Exit {:?} {:?}", bus.id, e);
+            sched.kill(e.pid);
+            Context::Nil
+        }
+
+        Some(&Message::Steal(ref s)) if s.to == bus.id => {
+            println!("InterCore Steal {:?} {:?}", bus.id, s);
+            sched.give(s);
+            Context::Nil
+        }
+
+        Some(&Message::Load(ref l)) if l.to == bus.id => {
+            sched.loads.insert(l.from, (l.load, Instant::now()));
+            Context::Nil
+        }
+
+        Some(&Message::Halt(ref h)) if h.to == bus.id => {
+            if h.from == bus.id {
+                sched.halt(h.code); // exit[] here
+            } else if sched.halting.is_none() {
+                sched.halting = Some(h.code);
+            }
+            Context::Nil
+        }
+        Some(&Message::AckWatch(ref a)) if a.to == bus.id => {
+            if a.link {
+                sched.watchers.entry(a.task_id).or_insert(vec![]).push(Watcher::Link(a.from, a.pid));
+            }
+            Context::NodeAck(a.task_id, 1)
+        }
+
+        Some(&Message::AckKill(ref a)) if a.to == bus.id => Context::NodeAck(a.task_id, 1),
+
+        Some(&Message::Metrics(ref m)) if m.to == bus.id => {
+            send(bus,
+                 Message::AckMetrics(AckMetrics {
+                     from: bus.id,
+                     to: m.from,
+                     task_id: m.task_id,
+                     corr: m.corr,
+                     stats: sched.metrics(),
                  }));
             Context::Nil
         }
 
+        Some(&Message::AckMetrics(ref a)) if a.to == bus.id && a.task_id == DUMP => {
+            sched.dumped(a);
+            Context::Nil
+        }
+
+        Some(&Message::AckMetrics(ref a)) if a.to == bus.id => Context::Cont(a.task_id, message.unwrap()),
+
+        Some(&Message::AckTasks(ref a)) if a.to == bus.id => Context::Cont(a.task_id, message.unwrap()),
+
+        Some(&Message::AckNames(ref a)) if a.to == bus.id => Context::Cont(a.task_id, message.unwrap()),
+
+        Some(&Message::Nack(ref n)) if n.to == bus.id => {
+            println!("InterCore Nack {:?} {:?}", bus.id, n);
+            Context::Fail(n.task_id, n.error.clone())
+        }
+
         Some(&Message::AckPub(ref a)) if a.to == bus.id => {
             println!("InterCore AckPub {:?} {:?}", bus.id, a);
-            let h = into_raw(sched);
-            let mut t = from_raw(h).tasks.get_mut(a.task_id).expect("no task");
             Context::NodeAck(a.task_id, a.result_id)
         }
 
@@ -102,12 +562,17 @@ pub fn intercore<'a>(sched: &'a mut Scheduler<'a>, message: Option<&'a Message>,
             if sb.pub_id < pubs.len() {
                 if let Some(p) = pubs.get_mut(sb.pub_id as usize) {
                     let subscriber = p.subscribe();
+                    if let Some(b) = bus.bells.get(sb.from) {
+                        sched.queues.wake(sb.pub_id, b.clone());
+                    }
                     let message = Message::AckSub(AckSub {
                         from: bus.id,
                         to: sb.from,
                         task_id: sb.task_id,
+                        corr: sb.corr,
                         result_id: subscriber.token,
-                        s: subscriber,
+                        ty: sched.queues.publisher_type(sb.pub_id).expect("untyped publisher"),
+                        s: Some(subscriber),
                     });
                     send(bus, message);
                 }
@@ -117,15 +582,10 @@ pub fn intercore<'a>(sched: &'a mut Scheduler<'a>, message: Option<&'a Message>,
 
         Some(&Message::AckSub(ref a)) if a.to == bus.id => {
             println!("InterCore AckSub {:?} {:?}", bus.id, a);
-            let sub_index;
-            {
-                let subs = sched.queues.subscribers();
-                subs.push(a.s.clone());
-                sub_index = subs.len() - 1;
-            }
-            let h = into_raw(sched);
-            let mut t = from_raw(h).tasks.get_mut(a.task_id).expect("no task");
-            Context::NodeAck(a.task_id, sub_index)
+            match a.s {
+                Some(ref s) => Context::NodeAck(a.task_id, sched.queues.add_subscriber(s.clone(), a.ty)),
+                None => Context::Fail(a.task_id, "a subscriber read from bytes has no queue behind it".to_string()),
+            }
         }
 
         Some(x) => {
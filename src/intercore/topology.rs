@@ -0,0 +1,330 @@
+// How the cores are wired: core count, bus capacity, shape of the bus,
+// which core runs the shell (BSP), the CPU each core is pinned to
+// and what a core does with messages when its bus is full.
+// Pinning to `auto picks the CPUs one per physical core, node by node.
+// A pinned core's ring is put on its NUMA node.
+// An idle core spins for `spin` empty rounds, then sleeps at most `sleep` ms on its bell.
+// A core with `busy` runnable tasks lets idle cores steal its new spawns, 0 turns it off.
+// With a `stats` port the BSP serves every core's counters to websocket clients each second.
+// Read from flags (-cores 8 -cap 64 -shape ring -bsp 0 -pin 0,2,4,6 -overflow drop -outbox 256 -spin 0 -sleep 50 -busy 4 -stats 9001)
+// or from an O boot script given by -topo (cores:8;shape:`ring;pin:0 2 4 6;overflow:`drop;spin:0;busy:4;stats:9001).
+
+use std::thread;
+use std::sync::mpsc;
+use std::fs::File;
+use std::io::Read;
+use args;
+use sys;
+use sys::cpu;
+use handle::{self, UnsafeShared};
+use commands::ast::{AST, Atom, Value};
+use streams::interpreter::Interpreter;
+use reactors::task::Context;
+use reactors::scheduler::{Scheduler, SPIN, SLEEP, BUSY};
+use std::time::Duration;
+use std::net::SocketAddr;
+use intercore::bus::{Bell, Channel, Memory, Overflow, OUTBOX};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Mesh, // every core hears every other
+    Star, // cores hear the BSP only, the BSP relays between them
+    Ring, // each core hears the previous one and relays around
+}
+
+impl Shape {
+    pub fn from_name(s: &str) -> Option<Shape> {
+        match s {
+            "mesh" => Some(Shape::Mesh),
+            "star" => Some(Shape::Star),
+            "ring" => Some(Shape::Ring),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topology {
+    pub cores: usize,
+    pub cap: usize,
+    pub shape: Shape,
+    pub bsp: usize,
+    pub pin: Vec<usize>, // cpu of each core, unpinned when empty
+    pub auto: bool, // pin from the machine's topology
+    pub overflow: Overflow,
+    pub outbox: usize,
+    pub spin: usize,
+    pub sleep: u64, // ms
+    pub busy: usize,
+    pub stats: Option<u16>, // websocket port of the stats dump
+}
+
+const STATS_EVERY: u64 = 1000; // ms
+
+impl Default for Topology {
+    fn default() -> Topology {
+        Topology {
+            cores: 4,
+            cap: 12,
+            shape: Shape::Mesh,
+            bsp: 0,
+            pin: vec![],
+            auto: false,
+            overflow: Overflow::Block,
+            outbox: OUTBOX,
+            spin: SPIN,
+            sleep: SLEEP,
+            busy: BUSY,
+            stats: None,
+        }
+    }
+}
+
+fn number(flag: &str, s: &str) -> usize {
+    s.trim().parse().expect(&format!("{} expects a number, got {:?}", flag, s))
+}
+
+fn port(flag: &str, n: i64) -> u16 {
+    if n <= 0 || n > 65535 {
+        panic!("{} expects a port in 1..65535, got {}", flag, n);
+    }
+    n as u16
+}
+
+impl Topology {
+    pub fn from_args(p: &args::Parser) -> Topology {
+        let mut t = match p.get("-topo", true) {
+            Ok(Some(f)) => {
+                let mut src = String::new();
+                File::open(f)
+                    .expect(&format!("Can't open file {:?}.", f))
+                    .read_to_string(&mut src)
+                    .expect(&format!("Can't load src: {:?}", f));
+                Topology::from_script(&src)
+            }
+            _ => Topology::default(),
+        };
+        if let Ok(Some(x)) = p.get("-cores", true) {
+            t.cores = number("-cores", x);
+        }
+        if let Ok(Some(x)) = p.get("-cap", true) {
+            t.cap = number("-cap", x);
+        }
+        if let Ok(Some(x)) = p.get("-bsp", true) {
+            t.bsp = number("-bsp", x);
+        }
+        if let Ok(Some(x)) = p.get("-shape", true) {
+            t.shape = Shape::from_name(x).expect(&format!("Unknown shape: {}", x));
+        }
+        match p.get("-pin", true) {
+            Ok(Some("auto")) => t.auto = true,
+            Ok(Some(x)) => {
+                t.auto = false;
+                t.pin = x.split(',').map(|c| number("-pin", c)).collect()
+            }
+            _ => (),
+        }
+        if let Ok(Some(x)) = p.get("-overflow", true) {
+            t.overflow = Overflow::from_name(x).expect(&format!("Unknown overflow policy: {}", x));
+        }
+        if let Ok(Some(x)) = p.get("-outbox", true) {
+            t.outbox = number("-outbox", x);
+        }
+        if let Ok(Some(x)) = p.get("-spin", true) {
+            t.spin = number("-spin", x);
+        }
+        if let Ok(Some(x)) = p.get("-sleep", true) {
+            t.sleep = number("-sleep", x) as u64;
+        }
+        if let Ok(Some(x)) = p.get("-busy", true) {
+            t.busy = number("-busy", x);
+        }
+        if let Ok(Some(x)) = p.get("-stats", true) {
+            t.stats = Some(port("-stats", number("-stats", x) as i64));
+        }
+        t.check();
+        t
+    }
+
+    pub fn from_script(src: &str) -> Topology {
+        // the script runs in a scratch interpreter, its globals describe the topology
+        let mut mem = Memory::new();
+        let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+        let code = h.borrow_mut().parse(&src.to_string());
+        h.borrow_mut().run(code, Context::Nil, None).expect("Boot script failed");
+        let i = h.borrow();
+        let get = |name: &str| match i.arena.name_id(name) {
+            Some(n) => {
+                match i.env.get(n, i.env.last()) {
+                    Some((&AST::Atom(Atom::Value(ref v)), _)) => Some(v.clone()),
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+
+        let mut t = Topology::default();
+        if let Some(Value::Number(n)) = get("cores") {
+            t.cores = n as usize;
+        }
+        if let Some(Value::Number(n)) = get("cap") {
+            t.cap = n as usize;
+        }
+        if let Some(Value::Number(n)) = get("bsp") {
+            t.bsp = n as usize;
+        }
+        if let Some(Value::SymbolInt(s)) = get("shape") {
+            let name = i.arena.symbol_name(s).unwrap_or(String::new());
+            t.shape = Shape::from_name(&name).expect(&format!("Unknown shape: `{}", name));
+        }
+        match get("pin") {
+            Some(Value::VecInt(v)) => t.pin = v.iter().map(|&c| c as usize).collect(),
+            Some(Value::Number(c)) => t.pin = vec![c as usize],
+            Some(Value::SymbolInt(s)) if i.arena.symbol_name(s) == Some("auto".to_string()) => t.auto = true,
+            _ => (),
+        }
+        if let Some(Value::SymbolInt(s)) = get("overflow") {
+            let name = i.arena.symbol_name(s).unwrap_or(String::new());
+            t.overflow = Overflow::from_name(&name).expect(&format!("Unknown overflow policy: `{}", name));
+        }
+        if let Some(Value::Number(n)) = get("outbox") {
+            t.outbox = n as usize;
+        }
+        if let Some(Value::Number(n)) = get("spin") {
+            t.spin = n as usize;
+        }
+        if let Some(Value::Number(n)) = get("sleep") {
+            t.sleep = n as u64;
+        }
+        if let Some(Value::Number(n)) = get("busy") {
+            t.busy = n as usize;
+        }
+        if let Some(Value::Number(n)) = get("stats") {
+            t.stats = Some(port("stats", n));
+        }
+        t.check();
+        t
+    }
+
+    fn check(&self) {
+        if self.cores == 0 || self.bsp >= self.cores {
+            panic!("BSP core {} is out of {} cores", self.bsp, self.cores);
+        }
+        if !self.auto && !self.pin.is_empty() && self.pin.len() != self.cores {
+            panic!("{} cpus pinned for {} cores", self.pin.len(), self.cores);
+        }
+    }
+
+    fn hears(&self, i: usize, j: usize) -> bool {
+        i == j ||
+        match self.shape {
+            Shape::Mesh => true,
+            Shape::Star => i == self.bsp || j == self.bsp,
+            Shape::Ring => j == (i + self.cores - 1) % self.cores,
+        }
+    }
+
+    fn relays(&self, i: usize) -> Vec<usize> {
+        match self.shape {
+            Shape::Mesh => vec![],
+            Shape::Star if i == self.bsp => (0..self.cores).filter(|&j| j != i).collect(),
+            Shape::Star => vec![],
+            Shape::Ring => (0..self.cores).filter(|&j| j != i).collect(),
+        }
+    }
+
+    pub fn channels(&self) -> Vec<Channel> {
+        let mut channels: Vec<Channel> = (0..self.cores).map(|i| Channel::new(i, self.cap)).collect();
+        for i in 0..self.cores {
+            for j in 0..self.cores {
+                if self.hears(i, j) {
+                    let s = channels[j].publisher.subscribe();
+                    let bell = channels[i].bell.clone();
+                    channels[i].listen(j, s);
+                    channels[j].rings.push(bell);
+                }
+            }
+            channels[i].relay = self.relays(i);
+            channels[i].overflow = self.overflow;
+            channels[i].limit = self.outbox;
+        }
+        let bells: Vec<Bell> = channels.iter().map(|c| c.bell.clone()).collect();
+        for c in channels.iter_mut() {
+            c.bells = bells.clone();
+        }
+        channels
+    }
+
+    pub fn placement(&self, cpus: &[cpu::Cpu]) -> Vec<Option<(usize, usize)>> {
+        // (cpu, node) of each core
+        let pin = if self.auto { cpu::spread(cpus, self.cores) } else { self.pin.clone() };
+        (0..self.cores).map(|id| pin.get(id).map(|&c| (c, cpu::node_of(cpus, c)))).collect()
+    }
+
+    pub fn park<'a>(&self) -> Scheduler<'a> {
+        // every core but the BSP gets its own thread, the BSP is returned to the caller.
+        // A core pins itself before it touches its queues, so they land on its node too.
+        let cpus = cpu::cpus();
+        let place = self.placement(&cpus);
+        let mut nodes: Vec<usize> = cpus.iter().map(|c| c.node).collect();
+        nodes.sort();
+        nodes.dedup();
+        println!("{} cores on {} cpus, {} NUMA nodes", self.cores, cpus.len(), nodes.len());
+        sys::block_signals(); // before the cores start, only the BSP reads them
+        let mut bsp = None;
+        let mut bound = false;
+        let mut threads = vec![];
+        let (stopped, acks) = mpsc::channel();
+        for (id, channel) in self.channels().into_iter().enumerate() {
+            let at = place[id];
+            let root = self.bsp;
+            let (spin, sleep, busy) = (self.spin, Duration::from_millis(self.sleep), self.busy);
+            let peers: Vec<usize> = (0..self.cores).filter(|&j| j != id).collect();
+            let stopped = stopped.clone();
+            if id == root {
+                if let Some((c, n)) = at {
+                    sys::set_affinity(c);
+                    bound = channel.bind(n);
+                }
+                bsp = Some(channel);
+                continue;
+            }
+            let t = thread::Builder::new()
+                .name(format!("core_{}", id))
+                .spawn(move || {
+                    let mut bound = false;
+                    if let Some((c, n)) = at {
+                        sys::set_affinity(c);
+                        bound = channel.bind(n);
+                    }
+                    let mut sched = Scheduler::with_channel2(channel);
+                    sched.bound = bound;
+                    sched.bsp = root;
+                    sched.spin = spin;
+                    sched.sleep = sleep;
+                    sched.busy = busy;
+                    sched.peers = peers;
+                    sched.place = at;
+                    sched.run();
+                    stopped.send(id).ok();
+                })
+                .expect("Can't spawn new thread!");
+            threads.push((id, t));
+        }
+        let mut sched = Scheduler::with_channel2(bsp.expect("No BSP"));
+        sched.threads = threads;
+        sched.stopped = Some(acks);
+        sched.bsp = self.bsp;
+        sched.spin = self.spin;
+        sched.sleep = Duration::from_millis(self.sleep);
+        sched.busy = self.busy;
+        sched.peers = (0..self.cores).filter(|&j| j != self.bsp).collect();
+        sched.place = place[self.bsp];
+        sched.bound = bound;
+        if let Some(port) = self.stats {
+            let addr: SocketAddr = format!("127.0.0.1:{}", port).parse().expect("Bad stats port");
+            sched.dump_to(&addr, Duration::from_millis(STATS_EVERY));
+        }
+        sched
+    }
+}
@@ -0,0 +1,323 @@
+// Binary encoding of values and code for the bus, journals and sockets.
+// Integers are little endian, vectors and strings are length (u32) prefixed.
+// Code that is not plain data (lambdas, tables, calls) travels as O source.
+
+use std::mem;
+use commands::ast::{self, AST, Atom, Arena, Value};
+use commands::source;
+
+macro_rules! try_opt {
+    ($e:expr) => {
+        match $e {
+            Some(x) => x,
+            None => return None,
+        }
+    };
+}
+
+const NIL: u8 = 0;
+const SYMBOL: u8 = 1;
+const SEQUENCE: u8 = 2;
+const BOOL: u8 = 3;
+const BYTE: u8 = 4;
+const SHORT: u8 = 5;
+const INT: u8 = 6;
+const LONG: u8 = 7;
+const REAL: u8 = 8;
+const FLOAT: u8 = 9;
+const VEC_BOOL: u8 = 10;
+const VEC_BYTE: u8 = 11;
+const VEC_SHORT: u8 = 12;
+const VEC_INT: u8 = 13;
+const VEC_LONG: u8 = 14;
+const VEC_REAL: u8 = 15;
+const VEC_FLOAT: u8 = 16;
+const LIST: u8 = 17;
+const IOVERB: u8 = 18;
+const SYMBOL_NAME: u8 = 19;
+const SEQUENCE_TEXT: u8 = 20;
+
+const AST_VALUE: u8 = 0;
+const AST_VECTOR: u8 = 1;
+const AST_SOURCE: u8 = 2;
+
+pub fn put_u8(out: &mut Vec<u8>, x: u8) {
+    out.push(x)
+}
+
+pub fn put_u16(out: &mut Vec<u8>, x: u16) {
+    put_uint(out, x as u64, 2)
+}
+
+pub fn put_u32(out: &mut Vec<u8>, x: u32) {
+    put_uint(out, x as u64, 4)
+}
+
+pub fn put_u64(out: &mut Vec<u8>, x: u64) {
+    put_uint(out, x, 8)
+}
+
+fn put_uint(out: &mut Vec<u8>, x: u64, n: usize) {
+    for i in 0..n {
+        out.push((x >> (8 * i)) as u8)
+    }
+}
+
+pub fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len() as u32);
+    out.extend_from_slice(s.as_bytes())
+}
+
+pub struct Reader<'b> {
+    buf: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Reader<'b> {
+    pub fn new(buf: &'b [u8]) -> Self {
+        Reader { buf: buf, pos: 0 }
+    }
+
+    pub fn done(&self) -> bool {
+        self.pos == self.buf.len()
+    }
+
+    pub fn left(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn uint(&mut self, n: usize) -> Option<u64> {
+        if self.pos + n > self.buf.len() {
+            return None;
+        }
+        let mut x = 0u64;
+        for i in 0..n {
+            x |= (self.buf[self.pos + i] as u64) << (8 * i);
+        }
+        self.pos += n;
+        Some(x)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.uint(1).map(|x| x as u8)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.uint(2).map(|x| x as u16)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.uint(4).map(|x| x as u32)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.uint(8)
+    }
+
+    pub fn str(&mut self) -> Option<String> {
+        let n = try_opt!(self.u32()) as usize;
+        if self.pos + n > self.buf.len() {
+            return None;
+        }
+        let s = String::from_utf8(self.buf[self.pos..self.pos + n].to_vec()).ok();
+        self.pos += n;
+        s
+    }
+}
+
+macro_rules! put_vec {
+    ($out:expr, $tag:expr, $v:expr, $put:expr) => {{
+        put_u8($out, $tag);
+        put_u32($out, $v.len() as u32);
+        for &x in $v.iter() {
+            ($put)($out, x)
+        }
+    }};
+}
+
+macro_rules! read_vec {
+    ($r:expr, $read:expr) => {{
+        // every item takes a byte at least, a longer count is a broken frame
+        let n = try_opt!($r.u32());
+        if n as usize > $r.left() {
+            return None;
+        }
+        let mut v = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            v.push(try_opt!(($read)($r)))
+        }
+        v
+    }};
+}
+
+fn f32_bits(x: f32) -> u32 {
+    unsafe { mem::transmute(x) }
+}
+
+fn f64_bits(x: f64) -> u64 {
+    unsafe { mem::transmute(x) }
+}
+
+fn real(r: &mut Reader) -> Option<f32> {
+    r.u32().map(|x| unsafe { mem::transmute::<u32, f32>(x) })
+}
+
+fn float(r: &mut Reader) -> Option<f64> {
+    r.u64().map(|x| unsafe { mem::transmute::<u64, f64>(x) })
+}
+
+pub fn value(v: &Value, arena: Option<&Arena>, out: &mut Vec<u8>) {
+    // with an arena interned symbols and sequences are written as text,
+    // without one they keep the ids of the interpreter they came from
+    match *v {
+        Value::Nil => put_u8(out, NIL),
+        Value::SymbolInt(s) => {
+            match arena.and_then(|a| a.symbol_name(s)) {
+                Some(name) => {
+                    put_u8(out, SYMBOL_NAME);
+                    put_str(out, &name)
+                }
+                None => {
+                    put_u8(out, SYMBOL);
+                    put_u16(out, s)
+                }
+            }
+        }
+        Value::SequenceInt(s) => {
+            match arena.and_then(|a| a.sequence_name(s)) {
+                Some(text) => {
+                    put_u8(out, SEQUENCE_TEXT);
+                    put_str(out, &text)
+                }
+                None => {
+                    put_u8(out, SEQUENCE);
+                    put_u16(out, s)
+                }
+            }
+        }
+        Value::Bool(b) => {
+            put_u8(out, BOOL);
+            put_u8(out, b as u8)
+        }
+        Value::Byte(b) => {
+            put_u8(out, BYTE);
+            put_u8(out, b)
+        }
+        Value::Short(n) => {
+            put_u8(out, SHORT);
+            put_u16(out, n as u16)
+        }
+        Value::Int32(n) => {
+            put_u8(out, INT);
+            put_u32(out, n as u32)
+        }
+        Value::Number(n) => {
+            put_u8(out, LONG);
+            put_u64(out, n as u64)
+        }
+        Value::Real(n) => {
+            put_u8(out, REAL);
+            put_u32(out, f32_bits(n))
+        }
+        Value::Float(n) => {
+            put_u8(out, FLOAT);
+            put_u64(out, f64_bits(n))
+        }
+        Value::VecBool(ref v) => put_vec!(out, VEC_BOOL, v, |o: &mut Vec<u8>, x: bool| put_u8(o, x as u8)),
+        Value::VecByte(ref v) => put_vec!(out, VEC_BYTE, v, put_u8),
+        Value::VecShort(ref v) => put_vec!(out, VEC_SHORT, v, |o: &mut Vec<u8>, x: i16| put_u16(o, x as u16)),
+        Value::VecInt32(ref v) => put_vec!(out, VEC_INT, v, |o: &mut Vec<u8>, x: i32| put_u32(o, x as u32)),
+        Value::VecInt(ref v) => put_vec!(out, VEC_LONG, v, |o: &mut Vec<u8>, x: i64| put_u64(o, x as u64)),
+        Value::VecReal(ref v) => put_vec!(out, VEC_REAL, v, |o: &mut Vec<u8>, x: f32| put_u32(o, f32_bits(x))),
+        Value::VecFloat(ref v) => put_vec!(out, VEC_FLOAT, v, |o: &mut Vec<u8>, x: f64| put_u64(o, f64_bits(x))),
+        Value::List(ref v) => {
+            put_u8(out, LIST);
+            put_u32(out, v.len() as u32);
+            for x in v {
+                value(x, arena, out)
+            }
+        }
+        Value::Ioverb(ref s) => {
+            put_u8(out, IOVERB);
+            put_str(out, s)
+        }
+    }
+}
+
+pub fn read_value(r: &mut Reader, arena: Option<&Arena>) -> Option<Value> {
+    Some(match try_opt!(r.u8()) {
+        NIL => Value::Nil,
+        SYMBOL => Value::SymbolInt(try_opt!(r.u16())),
+        SEQUENCE => Value::SequenceInt(try_opt!(r.u16())),
+        SYMBOL_NAME => {
+            let s = try_opt!(r.str());
+            match try_opt!(arena).intern_symbol(s) {
+                AST::Atom(Atom::Value(v)) => v,
+                _ => return None,
+            }
+        }
+        SEQUENCE_TEXT => {
+            let s = try_opt!(r.str());
+            match try_opt!(arena).intern_sequence(s) {
+                AST::Atom(Atom::Value(v)) => v,
+                _ => return None,
+            }
+        }
+        BOOL => Value::Bool(try_opt!(r.u8()) != 0),
+        BYTE => Value::Byte(try_opt!(r.u8())),
+        SHORT => Value::Short(try_opt!(r.u16()) as i16),
+        INT => Value::Int32(try_opt!(r.u32()) as i32),
+        LONG => Value::Number(try_opt!(r.u64()) as i64),
+        REAL => Value::Real(try_opt!(real(r))),
+        FLOAT => Value::Float(try_opt!(float(r))),
+        VEC_BOOL => Value::VecBool(read_vec!(r, |r: &mut Reader| r.u8().map(|x| x != 0))),
+        VEC_BYTE => Value::VecByte(read_vec!(r, |r: &mut Reader| r.u8())),
+        VEC_SHORT => Value::VecShort(read_vec!(r, |r: &mut Reader| r.u16().map(|x| x as i16))),
+        VEC_INT => Value::VecInt32(read_vec!(r, |r: &mut Reader| r.u32().map(|x| x as i32))),
+        VEC_LONG => Value::VecInt(read_vec!(r, |r: &mut Reader| r.u64().map(|x| x as i64))),
+        VEC_REAL => Value::VecReal(read_vec!(r, real)),
+        VEC_FLOAT => Value::VecFloat(read_vec!(r, float)),
+        LIST => Value::List(read_vec!(r, |r: &mut Reader| read_value(r, arena))),
+        IOVERB => Value::Ioverb(try_opt!(r.str())),
+        _ => return None,
+    })
+}
+
+pub fn ast(n: &AST, arena: &Arena, out: &mut Vec<u8>) {
+    match n {
+        &AST::Atom(Atom::Value(ref v)) => {
+            put_u8(out, AST_VALUE);
+            value(v, Some(arena), out)
+        }
+        &AST::Vector(ref v) => {
+            put_u8(out, AST_VECTOR);
+            put_u32(out, v.len() as u32);
+            for x in v {
+                ast(x, arena, out)
+            }
+        }
+        x => {
+            put_u8(out, AST_SOURCE);
+            put_str(out, &source::ast(x, arena))
+        }
+    }
+}
+
+pub fn read_ast<'a>(r: &mut Reader, arena: &'a Arena<'a>) -> Option<&'a AST<'a>> {
+    match try_opt!(r.u8()) {
+        AST_VALUE => read_value(r, Some(arena)).map(|v| arena.ast(AST::Atom(Atom::Value(v)))),
+        AST_VECTOR => {
+            let v = read_vec!(r, |r: &mut Reader| read_ast(r, arena).map(|x| x.clone()));
+            Some(arena.vec(v))
+        }
+        AST_SOURCE => {
+            let s = try_opt!(r.str());
+            match ast::try_parse(arena, &s) {
+                Ok(&AST::Vector(ref v)) if v.len() == 1 => Some(&v[0]),
+                Ok(x) => Some(x),
+                Err(_) => None,
+            }
+        }
+        _ => None,
+    }
+}
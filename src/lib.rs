@@ -53,6 +53,7 @@ pub mod streams;
 pub mod queues;
 pub mod handle;
 pub mod sys;
+pub mod timers;
 
 #[macro_use]
 extern crate libc;
@@ -64,4 +65,5 @@ extern crate core;
 extern crate bitflags;
 extern crate http_muncher;
 extern crate sha1;
-extern crate rustc_serialize;
\ No newline at end of file
+extern crate rustc_serialize;
+extern crate lalrpop_util;
\ No newline at end of file
@@ -12,6 +12,16 @@ use std::fmt;
 
 type Sequence = usize;
 
+// What a cursor's owner did: items and calls that went through, and calls that found
+// the ring full (publisher) or empty (subscriber).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Counters {
+    pub succ_items: u64,
+    pub succ_opers: u64,
+    pub fail_items: u64,
+    pub fail_opers: u64,
+}
+
 #[derive(Debug)]
 pub struct Cursor {
     padding0: [u64; 3],
@@ -59,6 +69,27 @@ impl Cursor {
     pub fn set_cache(&self, seq: Sequence) {
         self.cache.set(seq);
     }
+
+    #[inline]
+    fn succeeded(&self, items: usize) {
+        self.succ_items.set(self.succ_items.get() + items as u64);
+        self.succ_opers.set(self.succ_opers.get() + 1);
+    }
+
+    #[inline]
+    fn failed(&self, items: usize) {
+        self.fail_items.set(self.fail_items.get() + items as u64);
+        self.fail_opers.set(self.fail_opers.get() + 1);
+    }
+
+    pub fn counters(&self) -> Counters {
+        Counters {
+            succ_items: self.succ_items.get(),
+            succ_opers: self.succ_opers.get(),
+            fail_items: self.fail_items.get(),
+            fail_opers: self.fail_opers.get(),
+        }
+    }
 }
 
 pub struct UncheckedUnsafeArc<T> {
@@ -114,6 +145,17 @@ impl<T: Default> Publisher<T> {
         }
     }
 
+    pub fn with_pages(cap: usize) -> Self {
+        let mut cursors = vec![];
+        cursors.push(Cursor::new(0));
+
+        Publisher {
+            ring: Arc::new(RingBuffer::with_pages(cap).unwrap()),
+            next_seq_cache: Cell::new(0),
+            cursors: UncheckedUnsafeArc::new(cursors),
+        }
+    }
+
     pub fn with_mirror(name: CString, cap: usize) -> Self {
         let mut cursors = vec![];
         cursors.push(Cursor::new(0));
@@ -125,6 +167,10 @@ impl<T: Default> Publisher<T> {
         }
     }
 
+    pub fn memory(&self) -> (*const u8, usize) {
+        self.ring.memory()
+    }
+
     pub fn subscribe(&self) -> Subscriber<T> {
         let head = self.head().load();
         unsafe {
@@ -138,7 +184,17 @@ impl<T: Default> Publisher<T> {
         self.next_n(1).map(|vs| &mut vs[0])
     }
 
+    // One contiguous slice, which only holds across the wrap point on a mirrored ring.
     pub fn next_n(&self, n: usize) -> Option<&mut [T]> {
+        self.reserve(n).map(|seq| unsafe { self.ring.get_slice_mut(seq, n) })
+    }
+
+    // The batch as two slices split where the ring wraps, safe on any ring.
+    pub fn next_n_split(&self, n: usize) -> Option<(&mut [T], &mut [T])> {
+        self.reserve(n).map(|seq| unsafe { self.ring.get_slices_mut(seq, n) })
+    }
+
+    fn reserve(&self, n: usize) -> Option<Sequence> {
         let head = self.head();
         let cursors = self.cursors();
         let delta = n as Sequence;
@@ -146,23 +202,39 @@ impl<T: Default> Publisher<T> {
         let next_seq = curr_seq + delta;
         let cap = self.ring.cap();
 
+        if n > cap {
+            // more than the ring holds, however far behind the subscribers are
+            head.failed(n);
+            return None;
+        }
+
         if head.get_cache() + cap < next_seq {
             let mut min_tail = MAX;
             for tail in cursors.iter().skip(1) {
                 min_tail = min(min_tail, tail.load());
                 head.set_cache(min_tail);
                 if min_tail + cap < next_seq {
+                    head.failed(n);
                     return None;
                 }
             }
         }
         self.next_seq_cache.set(next_seq);
-        let slice = unsafe { self.ring.get_slice_mut(curr_seq, n) };
-        Some(slice)
+        Some(curr_seq)
     }
 
     pub fn commit(&self) {
-        self.head().store(self.next_seq_cache.get());
+        // counted here, a reservation that is never committed sent nothing
+        let head = self.head();
+        let next = self.next_seq_cache.get();
+        if next > head.load() {
+            head.succeeded(next - head.load());
+        }
+        head.store(next);
+    }
+
+    pub fn counters(&self) -> Counters {
+        self.head().counters()
     }
 
     #[inline]
@@ -235,35 +307,58 @@ impl<T: Default> Subscriber<T> {
         }
     }
 
+    pub fn cap(&self) -> usize {
+        self.ring.cap()
+    }
+
     pub fn recv(&self) -> Option<&T> {
         self.recv_n(1).map(|vs| &vs[0])
     }
 
+    // One contiguous slice, which only holds across the wrap point on a mirrored ring.
     pub fn recv_n(&self, n: usize) -> Option<&[T]> {
+        self.acquire(n).map(|seq| unsafe { self.ring.get_slice(seq, n) })
+    }
 
-        let tail = self.tail(self.token);
-        let head = self.tail(0);
+    // The batch as two slices split where the ring wraps, safe on any ring.
+    pub fn recv_n_split(&self, n: usize) -> Option<(&[T], &[T])> {
+        self.acquire(n).map(|seq| unsafe { self.ring.get_slices(seq, n) })
+    }
 
-        let tail_seq = tail.load();
-        let delta = n as Sequence;
-        let next_seq = tail_seq + delta;
+    pub fn recv_all(&self) -> Option<&[T]> {
+        self.acquire_all().map(|(seq, n)| unsafe { self.ring.get_slice(seq, n) })
+    }
 
+    pub fn recv_all_split(&self) -> Option<(&[T], &[T])> {
+        self.acquire_all().map(|(seq, n)| unsafe { self.ring.get_slices(seq, n) })
+    }
+
+    // A peek that takes nothing, so it is not counted as a failed receive.
+    pub fn ready(&self, n: usize) -> bool {
+        self.available(n)
+    }
+
+    fn available(&self, n: usize) -> bool {
+        let tail = self.tail(self.token);
+        let next_seq = tail.load() + n as Sequence;
         if next_seq > tail.get_cache() {
-            tail.set_cache(head.load());
-            if next_seq > tail.get_cache() {
-                // tail.fail_items.set(tail.fail_items.get() + n as u64);
-                // tail.fail_opers.set(tail.fail_opers.get() + 1 as u64);
-                return None;
-            }
+            tail.set_cache(self.tail(0).load());
         }
-
-        self.next_seq_cache.set(next_seq);
-        let slice = unsafe { self.ring.get_slice(tail_seq, n) };
-        Some(slice)
+        next_seq <= tail.get_cache()
     }
 
-    pub fn recv_all(&self) -> Option<&[T]> {
+    fn acquire(&self, n: usize) -> Option<Sequence> {
+        let tail = self.tail(self.token);
+        if !self.available(n) {
+            tail.failed(n);
+            return None;
+        }
+        let tail_seq = tail.load();
+        self.next_seq_cache.set(tail_seq + n as Sequence);
+        Some(tail_seq)
+    }
 
+    fn acquire_all(&self) -> Option<(Sequence, usize)> {
         let tail = self.tail(self.token);
         let head = self.tail(0);
 
@@ -271,18 +366,25 @@ impl<T: Default> Subscriber<T> {
         let head_seq = head.load();
 
         if tail_seq >= head_seq {
-            // tail.fail_items.set(tail.fail_items.get() + 1 as u64);
-            // tail.fail_opers.set(tail.fail_opers.get() + 1 as u64);
+            tail.failed(1);
             return None;
         } else {
             self.next_seq_cache.set(head_seq);
-            let slice = unsafe { self.ring.get_slice(tail_seq, head_seq - tail_seq) };
-            Some(slice)
+            Some((tail_seq, head_seq - tail_seq))
         }
     }
 
     pub fn commit(&self) {
-        self.tail(self.token).store(self.next_seq_cache.get());
+        let tail = self.tail(self.token);
+        let next = self.next_seq_cache.get();
+        if next > tail.load() {
+            tail.succeeded(next - tail.load());
+        }
+        tail.store(next);
+    }
+
+    pub fn counters(&self) -> Counters {
+        self.tail(self.token).counters()
     }
 
     #[inline]
@@ -522,6 +624,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_publisher_split_wrap() {
+        let publisher: Publisher<u64> = Publisher::with_capacity(4);
+        let subscriber = publisher.subscribe();
+
+        for i in 0..3 {
+            *publisher.next().unwrap() = i as u64;
+            publisher.commit();
+            subscriber.recv().unwrap();
+            subscriber.commit();
+        }
+
+        match publisher.next_n_split(3) {
+            Some((a, b)) => {
+                assert_eq!((a.len(), b.len()), (1, 2));
+                a[0] = 3;
+                b[0] = 4;
+                b[1] = 5;
+                publisher.commit();
+            }
+            None => assert!(false, "Queue had room for the batch!"),
+        }
+
+        match subscriber.recv_all_split() {
+            Some((a, b)) => {
+                assert_eq!(a, &[3u64]);
+                assert_eq!(b, &[4u64, 5]);
+                subscriber.commit();
+            }
+            None => assert!(false, "Queue was not empty but recv_all_split() returned nothing!"),
+        }
+    }
+
     #[test]
     fn test_publisher_one2one() {
         let mut publisher: Publisher<u64> = Publisher::with_capacity(8);
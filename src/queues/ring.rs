@@ -9,13 +9,17 @@ use std::io::Result;
 use libc;
 use std::mem;
 use std::default::Default;
+use std::cmp::min;
 
 #[repr(C)]
 pub struct RingBuffer<T> {
     buffer: RawVec<T>,
     mask: usize,
+    mapped: usize, // bytes mapped by with_pages, 0 when the heap owns the buffer
 }
 
+const PAGE: usize = 4096;
+
 impl<T: Default> RingBuffer<T> {
     pub fn with_capacity(cap: usize) -> Self {
         let adjusted = cap.next_power_of_two();
@@ -29,9 +33,39 @@ impl<T: Default> RingBuffer<T> {
         RingBuffer {
             buffer: v,
             mask: adjusted - 1,
+            mapped: 0,
         }
     }
 
+    pub fn with_pages(cap: usize) -> Result<RingBuffer<T>> {
+        // whole pages of its own, so binding them to a node moves nothing else
+        let adjusted = cap.next_power_of_two();
+        let size = (adjusted * mem::size_of::<T>() + PAGE - 1) & !(PAGE - 1);
+        let ptr = unsafe {
+            libc::mmap(ptr::null_mut(),
+                       size as libc::size_t,
+                       libc::PROT_READ | libc::PROT_WRITE,
+                       libc::MAP_ANON | libc::MAP_PRIVATE,
+                       -1,
+                       0)
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        for i in 0..adjusted {
+            unsafe {
+                ptr::write((ptr as *mut T).offset(i as isize), Default::default());
+            }
+        }
+        Ok(RingBuffer {
+            buffer: unsafe { RawVec::from_raw_parts(ptr as *mut T, adjusted) },
+            mask: adjusted - 1,
+            mapped: size,
+        })
+    }
+
     pub fn with_mirror(name: CString, cap: usize) -> Result<RingBuffer<T>> {
         let adjusted = cap.next_power_of_two();
         let mask = 4096 - 1;
@@ -97,6 +131,7 @@ impl<T: Default> RingBuffer<T> {
         Ok(RingBuffer {
             buffer: unsafe { RawVec::from_raw_parts(ptr as *mut T, adjusted) },
             mask: adjusted - 1,
+            mapped: 0,
         })
     }
 
@@ -104,6 +139,7 @@ impl<T: Default> RingBuffer<T> {
         RingBuffer {
             buffer: unsafe { RawVec::from_raw_parts(ptr, cap) },
             mask: cap - 1,
+            mapped: 0,
         }
     }
 
@@ -112,6 +148,11 @@ impl<T: Default> RingBuffer<T> {
         self.buffer.cap()
     }
 
+    pub fn memory(&self) -> (*const u8, usize) {
+        // the slots as bytes, for placing them on a NUMA node
+        (self.buffer.ptr() as *const u8, self.cap() * mem::size_of::<T>())
+    }
+
     #[inline]
     pub unsafe fn get(&self, pos: usize) -> &T {
         transmute(self.buffer.ptr().offset((pos & self.mask) as isize))
@@ -127,6 +168,24 @@ impl<T: Default> RingBuffer<T> {
         transmute(from_raw_parts_mut(self.buffer.ptr().offset((pos & self.mask) as isize), len))
     }
 
+    // A batch that runs past the last slot continues at the first one, so a ring that is
+    // not mirrored hands it out as two slices split at the wrap point.
+    #[inline]
+    pub unsafe fn get_slices(&self, pos: usize, len: usize) -> (&[T], &[T]) {
+        let start = pos & self.mask;
+        let first = min(len, self.cap() - start);
+        (transmute(from_raw_parts(self.buffer.ptr().offset(start as isize), first)),
+         transmute(from_raw_parts(self.buffer.ptr(), len - first)))
+    }
+
+    #[inline]
+    pub unsafe fn get_slices_mut(&self, pos: usize, len: usize) -> (&mut [T], &mut [T]) {
+        let start = pos & self.mask;
+        let first = min(len, self.cap() - start);
+        (transmute(from_raw_parts_mut(self.buffer.ptr().offset(start as isize), first)),
+         transmute(from_raw_parts_mut(self.buffer.ptr(), len - first)))
+    }
+
     #[inline]
     pub unsafe fn take(&self, pos: usize) -> T {
         ptr::read(self.buffer.ptr().offset((pos & self.mask) as isize))
@@ -138,6 +197,19 @@ impl<T: Default> RingBuffer<T> {
     }
 }
 
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        // mapped pages go back to the kernel, not to the heap
+        if self.mapped > 0 {
+            let b = mem::replace(&mut self.buffer, RawVec::new());
+            unsafe {
+                libc::munmap(b.ptr() as *mut libc::c_void, self.mapped as libc::size_t);
+            }
+            mem::forget(b);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +281,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ring_buffer_with_pages() {
+        let mut ring: RingBuffer<u64> = RingBuffer::with_pages(3).unwrap();
+        let (p, len) = ring.memory();
+        assert_eq!(p as usize % PAGE, 0);
+        assert_eq!(len, 32);
+        unsafe {
+            ring.store(5, 42u64);
+            assert_eq!(*ring.get(1), 42u64);
+            assert_eq!(*ring.get(2), 0u64);
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_get_slices() {
+        let mut ring: RingBuffer<u64> = RingBuffer::with_capacity(4);
+        unsafe {
+            ring.store(2, 42u64);
+            ring.store(3, 43u64);
+            ring.store(4, 44u64);
+
+            let (a, b) = ring.get_slices(2, 3);
+            assert_eq!(a, &[42, 43u64]);
+            assert_eq!(b, &[44u64]);
+
+            let (a, b) = ring.get_slices_mut(9, 2);
+            a[0] = 45u64;
+            assert_eq!(a, &[45, 42u64]);
+            assert_eq!(b, &[] as &[u64]);
+        }
+    }
+
     #[test]
     fn test_ring_buffer_from_raw_parts() {
         use std::mem;
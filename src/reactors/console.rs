@@ -54,8 +54,15 @@ impl<'a> Select<'a> for Console {
         io.register(self, s);
     }
 
-    fn select(&'a mut self, _: &'a mut IO, t: Token, buf: &mut [u8]) -> usize {
-        self.stdin.read(buf).expect("Console internal error.")
+    fn select(&'a mut self, io: &'a mut IO, t: Token, buf: &mut [u8]) -> usize {
+        // Ctrl-D reads nothing
+        match self.stdin.read(buf).expect("Console internal error.") {
+            0 => {
+                io.stop();
+                0
+            }
+            n => n,
+        }
     }
 
     fn finalize(&mut self) {
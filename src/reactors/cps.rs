@@ -1,14 +1,16 @@
-use reactors::task::{Task, Context, Poll, Error};
+use reactors::task::{Task, Context, Poll, Error, State};
 use streams::interpreter::*;
 use commands::ast::{Atom, AST};
 use handle::*;
 use intercore::bus::{send, Memory};
 use reactors::scheduler::Scheduler;
+use intercore::message::Message;
 
 pub struct CpsTask<'a> {
     pub interpreter: Interpreter<'a>,
     pub ast: Option<&'a AST<'a>>,
     task_id: usize,
+    waiting: bool,
 }
 
 impl<'a> CpsTask<'a> {
@@ -17,9 +19,40 @@ impl<'a> CpsTask<'a> {
             interpreter: Interpreter::new(mem_ptr).unwrap(),
             ast: None,
             task_id: 0,
+            waiting: false,
         }
     }
 
+    pub fn idle(&self) -> bool {
+        // nothing half evaluated, a timer may run code in it
+        match self.interpreter.registers {
+            Lazy::Start => !self.waiting && self.interpreter.blocked.is_none(),
+            _ => false,
+        }
+    }
+
+    pub fn runnable(&self) -> bool {
+        !self.waiting && self.interpreter.ready()
+    }
+
+    pub fn state(&self) -> State {
+        if self.waiting {
+            return State::Ack;
+        }
+        if self.interpreter.blocked.is_some() {
+            return State::Queue;
+        }
+        match self.interpreter.registers {
+            Lazy::Start => State::Sleeping,
+            _ => State::Runnable,
+        }
+    }
+
+    pub fn defines(&'a self, name: &str) -> bool {
+        let i = &self.interpreter;
+        i.arena.name_id(name).and_then(|n| i.env.get(n, i.env.last())).is_some()
+    }
+
     #[inline]
     fn run(&'a mut self,
            n: &'a AST<'a>,
@@ -33,6 +66,13 @@ impl<'a> CpsTask<'a> {
                 if let &Context::Intercore(msg) = ic {
                     match sched {
                         Some(ref s) => {
+                            from_raw(x).waiting = true;
+                            s.pending.expect(msg);
+                            if let &Message::Pub(ref p) = msg {
+                                if p.name != "" {
+                                    s.named.borrow_mut().insert(p.task_id);
+                                }
+                            }
                             send(&s.bus, msg.clone());
                             return Poll::Yield(ic.clone());
                         }
@@ -64,6 +104,7 @@ impl<'a> Task<'a> for CpsTask<'a> {
     }
 
     fn exec(&'a mut self, input: Option<&'a str>) {
+        self.waiting = false;
         match input {
             Some(i) => {
                 let s = i.to_string();
@@ -77,9 +118,17 @@ impl<'a> Task<'a> for CpsTask<'a> {
     fn poll(&'a mut self, c: Context<'a>, sched: &'a Scheduler<'a>) -> Poll<Context<'a>, Error> {
         match self.ast {
             Some(a) => {
+                self.interpreter.core = sched.bus.id;
                 match c.clone() {
                     Context::Node(n) => self.run(n, c, Some(sched)),
-                    Context::NodeAck(_, n) => self.run(a, c, Some(sched)),
+                    // an intercore reply is pending, nothing to resume with yet
+                    Context::Nil if self.waiting || !self.interpreter.ready() => Poll::Yield(Context::Nil),
+                    Context::NodeAck(..) |
+                    Context::Cont(..) |
+                    Context::Fail(..) => {
+                        self.waiting = false;
+                        self.run(a, c, Some(sched))
+                    }
                     Context::Nil => self.run(a, c, Some(sched)),
                     _ => Poll::Err(Error::WrongContext),
                 }
@@ -14,6 +14,36 @@ impl<'a> Job<'a> {
             Job::Cps(ref mut c) => c,
         }
     }
+
+    pub fn blocked(&self) -> bool {
+        match *self {
+            Job::Cps(ref c) => c.interpreter.blocked.is_some(),
+        }
+    }
+
+    pub fn idle(&self) -> bool {
+        match *self {
+            Job::Cps(ref c) => c.idle(),
+        }
+    }
+
+    pub fn runnable(&self) -> bool {
+        match *self {
+            Job::Cps(ref c) => c.runnable(),
+        }
+    }
+
+    pub fn state(&self) -> task::State {
+        match *self {
+            Job::Cps(ref c) => c.state(),
+        }
+    }
+
+    pub fn defines(&'a self, name: &str) -> bool {
+        match *self {
+            Job::Cps(ref c) => c.defines(name),
+        }
+    }
 }
 
 impl<'a> Task<'a> for Job<'a> {
@@ -1,8 +1,12 @@
 pub mod console;
 pub mod selector;
+pub mod signals;
 pub mod system;
 pub mod cps;
 pub mod scheduler;
+pub mod runq;
+pub mod sup;
+pub mod stats;
 pub mod task;
 pub mod ws;
 pub mod job;
\ No newline at end of file
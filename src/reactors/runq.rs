@@ -0,0 +1,61 @@
+// Runnable tasks by priority, the highest level is served first,
+// tasks of one level take turns.
+
+use std::collections::{VecDeque, HashSet};
+
+pub const LEVELS: usize = 8;
+pub const NORMAL: u8 = 4;
+
+pub struct RunQueue {
+    levels: Vec<VecDeque<usize>>,
+    queued: HashSet<usize>,
+}
+
+impl RunQueue {
+    pub fn new() -> Self {
+        RunQueue {
+            levels: (0..LEVELS).map(|_| VecDeque::new()).collect(),
+            queued: HashSet::new(),
+        }
+    }
+
+    pub fn push(&mut self, task: usize, prio: u8) {
+        if !self.queued.insert(task) {
+            return;
+        }
+        self.levels[level(prio)].push_back(task)
+    }
+
+    pub fn pop(&mut self) -> Option<usize> {
+        for l in self.levels.iter_mut().rev() {
+            if let Some(t) = l.pop_front() {
+                self.queued.remove(&t);
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    pub fn remove(&mut self, task: usize) -> bool {
+        if !self.queued.remove(&task) {
+            return false;
+        }
+        for l in self.levels.iter_mut() {
+            l.retain(|&t| t != task);
+        }
+        true
+    }
+
+    pub fn contains(&self, task: usize) -> bool {
+        self.queued.contains(&task)
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.iter().map(|l| l.len()).sum()
+    }
+}
+
+pub fn level(prio: u8) -> usize {
+    // priorities above the top level share it
+    (prio as usize).min(LEVELS - 1)
+}
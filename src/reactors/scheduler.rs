@@ -1,127 +1,1019 @@
-use reactors::task::{Task, Context, TaskId, T3, Termination};
+use reactors::task::{self, Task, Context, TaskId, T3, Termination, State};
 use reactors::job::Job;
+use reactors::runq::{RunQueue, NORMAL};
 use reactors::system::{IO, Async};
 use reactors::cps::CpsTask;
+use reactors::sup::{Supervisor, Strategy};
+use reactors::stats::{self, Stats};
+use reactors::ws::WsServer;
+use std::net::SocketAddr;
+use sys;
+use libc;
 use intercore::message::*;
-use intercore::bus::{Memory, Channel, send};
-use intercore::server::{intercore, delivery};
+use intercore::bus::{Memory, Channel, TypeId, send, force};
+use intercore::server::{intercore, delivery, Registered, REGISTRAR};
+use intercore::pending::{Pending, TIMEOUT};
 use queues::publisher::Publisher;
-use std::{thread, time};
-use handle::{from_raw, into_raw, UnsafeShared};
+use std::thread;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use handle::{from_raw, into_raw, use_, UnsafeShared};
 use reactors::console::Console;
-use reactors::selector::Selector;
+use reactors::signals::Signals;
+use reactors::selector::{Selector, Slot};
 use std::str;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::cell::RefCell;
+use streams::verb;
+use commands::ast::Value;
+use reactors::task::Poll;
+use timers::timer::Timeout;
 
-const TASKS_MAX_CNT: usize = 256;
+pub const TASKS_MAX_CNT: usize = 256;
+const BUSY_RETRY: u64 = 10; // ms, a timer whose task is running tries again
+pub const SPIN: usize = 1000; // empty rounds before a core parks
+pub const SLEEP: u64 = 100; // ms, longest park
+const DOZE: u64 = 1; // ms, park while someone polls for progress
+pub const BUSY: usize = 4; // runnable tasks before new spawns wait to be stolen
+const STEAL_RETRY: u64 = 10; // ms between the asks of an idle core
+const LOAD_TTL: u64 = 100; // ms a peer's load is trusted, then it is asked again
+const HOLD: u64 = 50; // ms a spawn waits for a thief before it starts here anyway
+const DRAIN: u64 = 20; // ms of a quiet bus after which a stopping core leaves
+const STOP: u64 = 1000; // ms a stopping core drains its bus, and the BSP waits for the others
+pub const DUMP: usize = !0; // the task stats requested for the websocket dump are answered to
+
+// .z.ts runs every ms given to every[ms], as q's \t does
+pub const ZTS: &'static str = ".z.ts";
+
+// .z.sig gets the number of a signal the BSP caught, SIGHUP to reload, SIGTERM to flush
+pub const ZSIG: &'static str = ".z.sig";
+
+// A timer runs its code in the task that set it, once that task is idle
+pub struct Alarm {
+    pub task: usize,
+    pub every: Option<Duration>,
+    pub txt: String,
+    pub timeout: Option<Timeout>,
+}
+
+// Autobalancing: a core with `busy` runnable tasks holds new spawns back, idle cores
+// ask the most loaded peer they know of for one. Running tasks never move, their
+// arenas and local publishers stay where they are.
+
+// A pid is generation * TASKS_MAX_CNT + slot, a reaped slot comes back with the next
+// generation so old pids never reach the new task. Results stay until the slot is reused,
+// the task itself is dropped at the start of the next round.
 
 pub struct Scheduler<'a> {
-    pub tasks: Vec<T3<Job<'a>>>,
+    pub tasks: Vec<Option<T3<Job<'a>>>>, // None once reaped
+    pub gens: Vec<usize>,
+    pub free: VecDeque<usize>, // reaped slots, oldest first
+    pub max_tasks: usize, // slots in use, at most TASKS_MAX_CNT
     pub bus: Channel,
     pub queues: Memory,
     pub io: IO,
+    pub results: HashMap<usize, Value>,
+    pub joins: Vec<Join>,
+    pub calls: Vec<(usize, Call)>,
+    pub registry: HashMap<String, Registered>,
+    pub named: RefCell<HashSet<usize>>, // tasks that asked for a named publisher
+    pub bsp: usize,
+    pub pending: Pending,
+    pub alarms: HashMap<usize, Alarm>,
+    pub signals: VecDeque<usize>, // caught while the shell was busy
+    pub alarm_id: usize,
+    pub ts: Option<usize>, // the alarm that runs .z.ts
+    pub spin: usize,
+    pub sleep: Duration,
+    pub runq: RunQueue,
+    pub sleepers: Vec<usize>, // blocked in rcv
+    pub watchers: HashMap<usize, Vec<Watcher>>, // told when the task ends
+    pub sups: HashMap<usize, Supervisor>,
+    pub downs: VecDeque<Down>, // monitor Downs waiting for room in their queue
+    pub sup_id: usize,
+    pub busy: usize, // 0 never holds spawns back
+    pub backlog: VecDeque<(Instant, Spawn)>, // spawns not started yet, idle cores may take them
+    pub peers: Vec<usize>,
+    pub loads: HashMap<usize, (usize, Instant)>, // last load heard from each peer
+    stolen: Option<Instant>, // when this core last asked for work
+    pub place: Option<(usize, usize)>, // cpu and NUMA node it is pinned to
+    pub bound: bool, // its bus ring was moved to that node
+    pub stats: Stats,
+    pub dump: Option<(Slot, Duration, Instant)>, // websocket, period, last dump
+    pub halting: Option<u8>, // the exit code once a Halt is heard
+    pub threads: Vec<(usize, thread::JoinHandle<()>)>, // the AP cores the BSP waits for
+    pub stopped: Option<mpsc::Receiver<usize>>, // the AP cores that left run
+    idle: usize,
+    dead: Vec<T3<Job<'a>>>, // reaped this round, the poll that ended them may still hold their values
 }
 
 impl<'a> Scheduler<'a> {
     pub fn with_channel(id: usize) -> Self {
-        let chan = Channel {
-            id: id,
-            publisher: //Publisher::with_mirror(CString::new(format!("/pub_{}", id)).unwrap(), 88),
-                       // NOTE: with_mirror is not working in tests
-                       Publisher::with_capacity(88),
-            subscribers: Vec::new(),
-        };
-        Scheduler {
-            tasks: Vec::with_capacity(TASKS_MAX_CNT),
-            bus: chan,
-            io: IO::new(),
-            queues: Memory::new(),
-        }
+        // NOTE: Publisher::with_mirror(CString::new(format!("/pub_{}", id)).unwrap(), 88)
+        //       is not working in tests
+        let chan = Channel::new(id, 88);
+        Scheduler::with_channel2(chan)
     }
 
     pub fn with_channel2(channel: Channel) -> Self {
+        let mut io = IO::new();
+        io.wakeup(channel.bell.notify());
         Scheduler {
             tasks: Vec::with_capacity(TASKS_MAX_CNT),
+            gens: Vec::with_capacity(TASKS_MAX_CNT),
+            free: VecDeque::new(),
+            max_tasks: TASKS_MAX_CNT,
             bus: channel,
-            io: IO::new(),
+            io: io,
             queues: Memory::new(),
+            results: HashMap::new(),
+            joins: Vec::new(),
+            calls: Vec::new(),
+            registry: HashMap::new(),
+            named: RefCell::new(HashSet::new()),
+            bsp: 0,
+            pending: Pending::new(Duration::from_millis(TIMEOUT)),
+            alarms: HashMap::new(),
+            signals: VecDeque::new(),
+            alarm_id: 0,
+            ts: None,
+            spin: SPIN,
+            sleep: Duration::from_millis(SLEEP),
+            runq: RunQueue::new(),
+            sleepers: Vec::new(),
+            watchers: HashMap::new(),
+            sups: HashMap::new(),
+            downs: VecDeque::new(),
+            sup_id: 0,
+            busy: 0,
+            backlog: VecDeque::new(),
+            peers: Vec::new(),
+            loads: HashMap::new(),
+            stolen: None,
+            place: None,
+            bound: false,
+            stats: Stats::default(),
+            dump: None,
+            halting: None,
+            threads: Vec::new(),
+            stopped: None,
+            idle: 0,
+            dead: Vec::new(),
         }
     }
 
-    pub fn spawn(&'a mut self, t: Job<'a>, l: Termination, input: Option<&'a str>) -> TaskId {
-        let last = self.tasks.len();
-        self.tasks.push(T3(t, l));
-        self.tasks.last_mut().expect("Scheduler: can't retrieve a task.").0.init(input, last);
-        TaskId(last, self.bus.id)
+    pub fn spawn(&'a mut self, t: Job<'a>, l: Termination, input: Option<&'a str>) -> Option<TaskId> {
+        // fresh slots first, then the one reaped longest ago, None when all are taken
+        let slot = if self.tasks.len() < self.max_tasks.min(TASKS_MAX_CNT) {
+            self.tasks.push(Some(T3(t, l, NORMAL)));
+            self.gens.push(0);
+            self.tasks.len() - 1
+        } else {
+            let s = match self.free.pop_front() {
+                Some(s) => s,
+                None => return None,
+            };
+            self.results.remove(&(self.gens[s] * TASKS_MAX_CNT + s));
+            self.gens[s] += 1;
+            self.tasks[s] = Some(T3(t, l, NORMAL));
+            s
+        };
+        let pid = self.gens[slot] * TASKS_MAX_CNT + slot;
+        self.stats.spawned += 1;
+        if let Some(ref mut t) = self.tasks[slot] {
+            t.0.init(input, pid);
+        }
+        if l == Termination::Recursive {
+            self.runq.push(pid, NORMAL);
+        }
+        Some(TaskId(pid, self.bus.id))
+    }
+
+    pub fn full(&self) -> bool {
+        self.tasks.len() >= self.max_tasks.min(TASKS_MAX_CNT) && self.free.is_empty()
+    }
+
+    pub fn slot(&self, pid: usize) -> Option<usize> {
+        let s = pid % TASKS_MAX_CNT;
+        match self.gens.get(s) {
+            Some(&g) if g == pid / TASKS_MAX_CNT && self.tasks[s].is_some() => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn task(&mut self, pid: usize) -> Option<&mut T3<Job<'a>>> {
+        match self.slot(pid) {
+            Some(s) => self.tasks[s].as_mut(),
+            None => None,
+        }
+    }
+
+    pub fn prioritize(&mut self, pid: usize, prio: u8) -> Option<u8> {
+        // returns the old priority, a queued task moves to its new level
+        let old = match self.task(pid) {
+            Some(t) => ::std::mem::replace(&mut t.2, prio),
+            None => return None,
+        };
+        if self.runq.remove(pid) {
+            self.runq.push(pid, prio);
+        }
+        Some(old)
+    }
+
+    pub fn route(&mut self, pid: usize) {
+        // after a poll a task goes where its state says, replies and Exec wake the others
+        let (state, prio) = match self.task(pid) {
+            Some(t) => (t.0.state(), t.2),
+            None => return,
+        };
+        match state {
+            State::Runnable => self.runq.push(pid, prio),
+            State::Queue if !self.sleepers.contains(&pid) => self.sleepers.push(pid),
+            _ => (),
+        }
+    }
+
+    pub fn settle(&mut self, pid: usize, r: &Poll<Context<'a>, task::Error>) {
+        // a recursive task that ended keeps its value
+        self.stats.steps += 1;
+        let recursive = match self.task(pid) {
+            Some(t) => t.1 == Termination::Recursive,
+            None => return,
+        };
+        match *r {
+            Poll::End(Context::Node(n)) if recursive => {
+                let v = verb::value(n).map(|v| v.into_owned()).unwrap_or(Value::Nil);
+                self.complete(pid, v);
+            }
+            Poll::End(_) if recursive => self.complete(pid, Value::Nil),
+            Poll::Err(_) if recursive => self.finish(pid, Value::Nil, true),
+            _ => self.route(pid),
+        }
     }
 
     pub fn exec(&'a mut self, t: TaskId, input: Option<&'a str>) {
-        self.tasks.get_mut(t.0).expect("Scheduler: can't retrieve a task.").0.exec(input);
+        self.task(t.0).expect("Scheduler: can't retrieve a task.").0.exec(input);
     }
 
-    pub fn terminate(&'a mut self, t: Termination, i: usize) {
-        if t == Termination::Recursive {
-            self.tasks.remove(i);
+    pub fn kill(&mut self, pid: usize) -> bool {
+        // a killed task ends with nil as a crash, whoever waits for it is answered
+        match self.task(pid).map(|t| t.1) {
+            Some(Termination::Recursive) => {
+                self.finish(pid, Value::Nil, true);
+                true
+            }
+            _ => false,
         }
     }
 
-    pub fn poll_bus(&mut self) {
+    fn reap(&mut self, pid: usize) {
+        if let Some(s) = self.slot(pid) {
+            if let Some(t) = self.tasks[s].take() {
+                self.dead.push(t);
+            }
+            self.free.push_back(s);
+            self.runq.remove(pid);
+            self.sleepers.retain(|&t| t != pid);
+            self.pending.forget(pid);
+            let alarms: Vec<usize> = self.alarms.iter().filter(|&(_, a)| a.task == pid).map(|(&id, _)| id).collect();
+            for id in alarms {
+                self.cancel(id);
+            }
+        }
+    }
+
+    pub fn listing(&self) -> Vec<(usize, String, u8)> {
+        // (pid, state, priority) of the live tasks
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter_map(|(s, t)| t.as_ref().map(|t| (s, t)))
+            .map(|(s, t)| {
+                let pid = self.gens[s] * TASKS_MAX_CNT + s;
+                let state = if self.runq.contains(pid) { State::Runnable } else { t.0.state() };
+                (pid, state.name().to_string(), t.2)
+            })
+            .collect()
+    }
+
+    pub fn poll_bus(&mut self) -> usize {
+        // the number of messages read
         let x = into_raw(self);
-        for s in &from_raw(x).bus.subscribers {
-            delivery(intercore(from_raw(x), s.recv(), &mut from_raw(x).bus),
+        let mut n = 0;
+        from_raw(x).bus.flush();
+        for (task, corr, e) in from_raw(x).bus.failures() {
+            if from_raw(x).pending.answer(task, corr) {
+                delivery(Context::Fail(task, e), from_raw(x));
+            }
+        }
+        for (task, corr) in from_raw(x).pending.expired() {
+            delivery(Context::Fail(task, format!("request {} timed out", corr)), from_raw(x));
+        }
+        for (k, s) in from_raw(x).bus.subscribers.iter().enumerate() {
+            let m = s.recv();
+            if let Some(m) = m {
+                if from_raw(x).bus.forwards(k, m) {
+                    match *m {
+                        Message::Halt(_) => force(&from_raw(x).bus, m.clone()),
+                        _ => send(&from_raw(x).bus, m.clone()),
+                    }
+                }
+            }
+            delivery(intercore(from_raw(x), m, &mut from_raw(x).bus),
                      from_raw(x));
             s.commit();
+            n += m.is_some() as usize;
+        }
+        from_raw(x).stats.messages += n as u64;
+        n
+    }
+
+    pub fn after(&mut self, task: usize, ms: usize, every: bool, txt: String) -> usize {
+        // every[ms] alone drives .z.ts, every[0] stops it
+        let zts = txt == ZTS;
+        if zts {
+            if let Some(id) = self.ts.take() {
+                self.cancel(id);
+            }
+            if ms == 0 {
+                return 0;
+            }
+        }
+        self.alarm_id += 1;
+        let id = self.alarm_id;
+        let d = Duration::from_millis(ms as u64);
+        let timeout = self.io.set_timeout(d, id);
+        self.alarms.insert(id,
+                           Alarm {
+                               task: task,
+                               every: if every { Some(d) } else { None },
+                               txt: txt,
+                               timeout: timeout,
+                           });
+        if zts {
+            self.ts = Some(id);
+        }
+        id
+    }
+
+    pub fn cancel(&mut self, id: usize) -> bool {
+        match self.alarms.remove(&id) {
+            Some(a) => {
+                if let Some(t) = a.timeout {
+                    self.io.cancel_timeout(&t);
+                }
+                if self.ts == Some(id) {
+                    self.ts = None;
+                }
+                true
+            }
+            None => false,
         }
     }
 
+    pub fn poll_timers(&mut self) -> usize {
+        let x = into_raw(self);
+        let fired = from_raw(x).io.timeouts();
+        for &id in &fired {
+            let (task, every) = match from_raw(x).alarms.get(&id) {
+                Some(a) => (a.task, a.every),
+                None => continue, // cancelled
+            };
+            let t = match from_raw(x).task(task) {
+                Some(t) => into_raw(t),
+                None => {
+                    self.cancel(id); // timers die with their task
+                    continue;
+                }
+            };
+            if !from_raw(t).0.idle() {
+                let t = from_raw(x).io.set_timeout(Duration::from_millis(BUSY_RETRY), id);
+                from_raw(x).alarms.get_mut(&id).expect("no alarm").timeout = t;
+                continue;
+            }
+            let a = from_raw(x).alarms.get_mut(&id).expect("no alarm");
+            if a.txt != ZTS || from_raw(t).0.defines(ZTS) {
+                let mut cmd = if a.txt == ZTS { format!("{}[]", ZTS) } else { a.txt.clone() };
+                from_raw(t).0.exec(Some(use_(&mut cmd)));
+                let r = from_raw(t).0.poll(Context::Nil, from_raw(x));
+                self.settle(task, &r);
+            }
+            // the run may have ended the task and its timers with it
+            match (every, from_raw(x).alarms.get_mut(&id)) {
+                (Some(d), Some(a)) => a.timeout = from_raw(x).io.set_timeout(d, id),
+                _ => {
+                    from_raw(x).alarms.remove(&id);
+                }
+            }
+        }
+        fired.len()
+    }
+
     pub fn handle_shell(&mut self, buf: Option<&'a str>, shell: TaskId) {
         if let Some(x) = buf {
             send(&self.bus, Message::Exec(shell.0, x.to_string()));
         }
     }
 
-    pub fn hibernate(&mut self) {
-        thread::sleep(time::Duration::from_millis(10)); // Green Peace
+    pub fn signal(&mut self, shell: TaskId, sig: usize) {
+        self.signals.push_back(sig);
+        self.poll_signals(shell);
+    }
+
+    pub fn poll_signals(&mut self, shell: TaskId) -> usize {
+        // signals wait for the shell to be idle, as timers do. Without .z.sig SIGINT and
+        // SIGTERM halt and SIGHUP is ignored, with it O decides
+        let x = into_raw(self);
+        let t = match from_raw(x).task(shell.0) {
+            Some(t) => into_raw(t),
+            None => return 0,
+        };
+        let mut n = 0;
+        while from_raw(t).0.idle() && self.halting.is_none() {
+            let sig = match self.signals.pop_front() {
+                Some(sig) => sig,
+                None => break,
+            };
+            n += 1;
+            if from_raw(t).0.defines(ZSIG) {
+                let mut cmd = format!("{}[{}]", ZSIG, sig);
+                from_raw(t).0.exec(Some(use_(&mut cmd)));
+                let r = from_raw(t).0.poll(Context::Nil, from_raw(x));
+                self.settle(shell.0, &r);
+            } else if sig == libc::SIGINT as usize || sig == libc::SIGTERM as usize {
+                self.halt((128 + sig) as u8); // as a shell reports it
+            }
+        }
+        n
+    }
+
+    pub fn load(&self) -> usize {
+        self.runq.len() + self.backlog.len()
+    }
+
+    pub fn hold(&mut self, v: &Spawn) -> bool {
+        // a busy core keeps a spawn for whoever is idle
+        if self.busy == 0 || v.pin || self.runq.len() < self.busy {
+            return false;
+        }
+        self.backlog.push_back((Instant::now(), v.clone()));
+        true
+    }
+
+    pub fn poll_backlog(&mut self) -> usize {
+        // held spawns nobody took start here once there is room or they waited long enough
+        let mut n = 0;
+        let hold = Duration::from_millis(HOLD);
+        loop {
+            let room = self.runq.len() + n < self.busy;
+            let due = match self.backlog.front() {
+                Some(&(t, _)) => room || t.elapsed() >= hold,
+                None => false,
+            };
+            if !due {
+                break;
+            }
+            let (_, mut v) = self.backlog.pop_front().unwrap();
+            v.to = self.bus.id;
+            v.pin = true;
+            send(&self.bus, Message::Spawn(v));
+            n += 1;
+        }
+        n
+    }
+
+    pub fn steal(&mut self) {
+        // an idle core asks a peer that looks busy, peers not heard from lately look busy
+        if self.busy == 0 || self.load() > 0 || self.peers.is_empty() {
+            return;
+        }
+        if self.stolen.map_or(false, |t| t.elapsed() < Duration::from_millis(STEAL_RETRY)) {
+            return;
+        }
+        let ttl = Duration::from_millis(LOAD_TTL);
+        let busy = self.busy;
+        let victim = {
+            let loads = &self.loads;
+            let load = |p: usize| match loads.get(&p) {
+                Some(&(l, t)) if t.elapsed() < ttl => l,
+                _ => busy,
+            };
+            match self.peers.iter().filter(|p| load(**p) >= busy).max_by_key(|p| load(**p)) {
+                Some(&p) => p,
+                None => return,
+            }
+        };
+        // the next ask starts with another peer
+        let p = self.peers.remove(0);
+        self.peers.push(p);
+        self.stolen = Some(Instant::now());
+        send(&self.bus,
+             Message::Steal(Steal {
+                 from: self.bus.id,
+                 to: victim,
+                 load: 0,
+             }));
+    }
+
+    pub fn give(&mut self, s: &Steal) {
+        // the oldest held spawn goes to the idle core, otherwise it learns our load
+        self.loads.insert(s.from, (s.load, Instant::now()));
+        match self.backlog.pop_front() {
+            Some((_, mut v)) => {
+                v.to = s.from;
+                v.pin = true;
+                send(&self.bus, Message::Spawn(v))
+            }
+            None => {
+                send(&self.bus,
+                     Message::Load(Load {
+                         from: self.bus.id,
+                         to: s.from,
+                         load: self.load(),
+                     }))
+            }
+        }
+    }
+
+    pub fn hibernate(&mut self, work: usize) {
+        // spins while work keeps coming, then sleeps on the bell until a publisher rings it
+        if work > 0 {
+            self.idle = 0;
+            return;
+        }
+        self.steal();
+        let t = Instant::now();
+        if self.idle < self.spin {
+            self.idle += 1;
+            thread::yield_now();
+            self.stats.idle += t.elapsed();
+            return;
+        }
+        // rcv deadlines, held spawns and a full bus are not rung for
+        let doze = self.bus.queued() > 0 || !self.backlog.is_empty() || !self.downs.is_empty() ||
+                   self.tasks.iter().any(|t| t.as_ref().map_or(false, |t| t.0.blocked()));
+        self.bus.bell.park();
+        if !self.bus.heard() {
+            self.io.park(if doze { Duration::from_millis(DOZE) } else { self.sleep });
+        }
+        self.bus.bell.unpark();
+        self.stats.idle += t.elapsed();
+    }
+
+    pub fn metrics(&self) -> Vec<(String, u64)> {
+        // this core's counters, then its bus and queues
+        let mut v = vec![("tasks", self.tasks.iter().filter(|t| t.is_some()).count() as u64),
+                         ("spawned", self.stats.spawned),
+                         ("steps", self.stats.steps),
+                         ("messages", self.stats.messages),
+                         ("io", self.stats.io),
+                         ("idle_ms", stats::millis(self.stats.idle)),
+                         ("sent", self.bus.traffic.sent.get()),
+                         ("retried", self.bus.traffic.retried.get()),
+                         ("dropped", self.bus.traffic.dropped.get()),
+                         ("lost", self.bus.traffic.lost.get())];
+        let c = self.bus.publisher.counters();
+        v.push(("bus_items", c.succ_items));
+        v.push(("bus_full", c.fail_opers));
+        let (mut items, mut empty) = (0, 0);
+        for s in self.queues.subscribers().iter() {
+            let c = s.counters();
+            items += c.succ_items;
+            empty += c.fail_opers;
+        }
+        v.push(("rcv_items", items));
+        v.push(("rcv_empty", empty));
+        v.into_iter().map(|(k, x)| (k.to_string(), x)).collect()
+    }
+
+    pub fn dump_to(&mut self, addr: &SocketAddr, every: Duration) {
+        // websocket clients get every core's stats each period
+        let slot = self.io.spawn(Selector::Ws(WsServer::new(addr)));
+        self.dump = Some((slot, every, Instant::now()));
     }
 
-    fn poll_tasks(&mut self) {
+    pub fn poll_dump(&mut self) -> usize {
+        let slot = match self.dump {
+            Some((slot, every, last)) if last.elapsed() >= every => slot,
+            _ => return 0,
+        };
+        if let Some(d) = self.dump.as_mut() {
+            d.2 = Instant::now();
+        }
+        let line = stats::json(self.bus.id, &self.metrics());
+        self.io.write(slot, line.as_bytes());
+        for &p in &self.peers {
+            send(&self.bus,
+                 Message::Metrics(Metrics {
+                     from: self.bus.id,
+                     to: p,
+                     task_id: DUMP,
+                     corr: 0,
+                 }));
+        }
+        1
+    }
+
+    pub fn dumped(&mut self, a: &AckMetrics) {
+        if let Some((slot, _, _)) = self.dump {
+            let line = stats::json(a.from, &a.stats);
+            self.io.write(slot, line.as_bytes());
+        }
+    }
+
+    pub fn poll_tasks(&mut self) -> usize {
+        // only runnable tasks are polled, the highest priority first
         let a = into_raw(self);
-        let l = from_raw(a).tasks.len();
-        for i in 1..l {
-            from_raw(a).tasks[i].0.poll(Context::Nil, from_raw(a));
+        let (ready, rest): (Vec<usize>, Vec<usize>) =
+            from_raw(a).sleepers.drain(..).partition(|&i| from_raw(a).task(i).map_or(false, |t| t.0.runnable()));
+        from_raw(a).sleepers = rest;
+        for i in ready {
+            if let Some(p) = from_raw(a).task(i).map(|t| t.2) {
+                from_raw(a).runq.push(i, p);
+            }
+        }
+        let n = from_raw(a).runq.len();
+        for _ in 0..n {
+            let i = match from_raw(a).runq.pop() {
+                Some(i) => i,
+                None => break,
+            };
+            let t = match from_raw(a).task(i) {
+                Some(t) => into_raw(t),
+                None => continue,
+            };
+            let r = from_raw(t).0.poll(Context::Nil, from_raw(a));
+            self.settle(i, &r);
+        }
+        n
+    }
+
+    pub fn complete(&mut self, pid: usize, v: Value) {
+        self.finish(pid, v, false)
+    }
+
+    pub fn finish(&mut self, pid: usize, v: Value, crashed: bool) {
+        // keep the final value of a task and answer everyone waiting on it
+        let (ready, rest): (Vec<Join>, Vec<Join>) = self.joins.drain(..).partition(|j| j.pid == pid);
+        self.joins = rest;
+        for j in ready {
+            send(&self.bus,
+                 Message::AckJoin(AckJoin {
+                     from: self.bus.id,
+                     to: j.from,
+                     task_id: j.task_id,
+                     corr: j.corr,
+                     value: v.clone(),
+                 }));
+        }
+        let (ready, rest): (Vec<(usize, Call)>, Vec<(usize, Call)>) = self.calls.drain(..).partition(|c| c.0 == pid);
+        self.calls = rest;
+        for (_, c) in ready {
+            send(&self.bus,
+                 Message::AckCall(AckCall {
+                     from: self.bus.id,
+                     to: c.from,
+                     task_id: c.task_id,
+                     corr: c.corr,
+                     value: v.clone(),
+                 }));
+        }
+        if self.named.borrow_mut().remove(&pid) {
+            send(&self.bus,
+                 Message::Unregister(Unregister {
+                     from: self.bus.id,
+                     to: REGISTRAR,
+                     task_id: pid,
+                 }));
+        }
+        for w in self.watchers.remove(&pid).unwrap_or(vec![]) {
+            send(&self.bus,
+                 Message::Down(Down {
+                     from: self.bus.id,
+                     to: w.core(),
+                     pid: pid,
+                     crashed: crashed,
+                     by: w,
+                 }));
+        }
+        let owned: Vec<usize> = self.sups.iter().filter(|&(_, s)| s.owner == pid).map(|(&id, _)| id).collect();
+        for id in owned {
+            self.unsupervise(id);
+        }
+        self.results.insert(pid, v);
+        self.reap(pid);
+    }
+
+    pub fn supervise(&mut self, owner: usize, strategy: Strategy, intensity: usize, period: Duration) -> usize {
+        self.sup_id += 1;
+        self.sups.insert(self.sup_id, Supervisor::new(owner, strategy, intensity, period));
+        self.sup_id
+    }
+
+    pub fn child(&mut self, sup: usize, core: usize, prio: u8, txt: String) -> Option<usize> {
+        let k = match self.sups.get_mut(&sup) {
+            Some(s) => s.add(core, prio, txt),
+            None => return None,
+        };
+        self.start(sup, k);
+        Some(k)
+    }
+
+    fn start(&mut self, sup: usize, k: usize) {
+        // a new generation of the child, the one running is stopped
+        let c = match self.sups.get_mut(&sup).and_then(|s| s.children.get_mut(k)) {
+            Some(c) => c,
+            None => return,
+        };
+        c.gen += 1;
+        if let Some(pid) = c.pid.take() {
+            send(&self.bus,
+                 Message::Exit(Exit {
+                     from: self.bus.id,
+                     to: c.core,
+                     pid: pid,
+                 }));
+        }
+        send(&self.bus,
+             Message::Start(Start {
+                 from: self.bus.id,
+                 to: c.core,
+                 sup: sup,
+                 child: k,
+                 gen: c.gen,
+                 prio: c.prio,
+                 txt: c.txt.clone(),
+             }));
+    }
+
+    pub fn started(&mut self, s: &Started) {
+        let current = match self.sups.get_mut(&s.sup) {
+            Some(x) => x.started(s.child, s.gen, s.pid),
+            None => false,
+        };
+        if !current {
+            send(&self.bus,
+                 Message::Exit(Exit {
+                     from: self.bus.id,
+                     to: s.from,
+                     pid: s.pid,
+                 }));
+        }
+    }
+
+    fn unsupervise(&mut self, sup: usize) {
+        // children stop with their supervisor
+        if let Some(s) = self.sups.remove(&sup) {
+            for c in s.children {
+                if let Some(pid) = c.pid {
+                    send(&self.bus,
+                         Message::Exit(Exit {
+                             from: self.bus.id,
+                             to: c.core,
+                             pid: pid,
+                         }));
+                }
+            }
+        }
+    }
+
+    pub fn down(&mut self, d: &Down) {
+        match d.by {
+            Watcher::Link(_, pid) => {
+                // the link back is dropped with it, a pid no longer watching d.pid was reused
+                let back = Watcher::Link(d.from, d.pid);
+                let linked = match self.watchers.get_mut(&pid) {
+                    Some(ws) => {
+                        let n = ws.len();
+                        ws.retain(|w| *w != back);
+                        ws.len() < n
+                    }
+                    None => false,
+                };
+                if linked && d.crashed {
+                    self.kill(pid);
+                }
+            }
+            Watcher::Monitor(..) => {
+                self.downs.push_back(d.clone());
+                self.poll_downs();
+            }
+            Watcher::Sup(_, sup, k) => {
+                let restarts = match self.sups.get_mut(&sup) {
+                    Some(s) => {
+                        if d.crashed {
+                            s.crashed(k, d.pid)
+                        } else {
+                            s.exited(k, d.pid); // a normal exit is not restarted
+                            return;
+                        }
+                    }
+                    None => return,
+                };
+                match restarts {
+                    Some(ks) => {
+                        for k in ks {
+                            self.start(sup, k);
+                        }
+                    }
+                    None => {
+                        // too many restarts, the owner crashes and its own supervisor decides
+                        let owner = self.sups[&sup].owner;
+                        println!("Supervisor {} gives up", sup);
+                        self.unsupervise(sup);
+                        self.kill(owner);
+                    }
+                }
+            }
         }
     }
 
+    fn tell(&mut self, d: &Down, pub_id: usize) -> bool {
+        // [core; pid; crashed] into a vector queue of the watching core, false while it is full
+        if self.queues.publisher_type(pub_id) != Some(TypeId::Vector) {
+            return true; // nobody to tell
+        }
+        match self.queues.publishers().get(pub_id) {
+            Some(p) => {
+                match p.next() {
+                    Some(slot) => {
+                        *slot = Value::VecInt(vec![d.from as i64, d.pid as i64, d.crashed as i64]);
+                        p.commit();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            None => true,
+        }
+    }
+
+    pub fn poll_downs(&mut self) -> usize {
+        // monitor Downs wait in order for room in their queue
+        let mut told = 0;
+        while let Some(d) = self.downs.pop_front() {
+            let pub_id = match d.by {
+                Watcher::Monitor(_, p) => p,
+                _ => continue,
+            };
+            if !self.tell(&d, pub_id) {
+                self.downs.push_front(d);
+                break;
+            }
+            told += 1;
+        }
+        told
+    }
+
+    pub fn halt(&mut self, code: u8) {
+        // the first core to stop tells every other, a Halt heard from a peer is not passed on
+        if self.halting.is_some() {
+            return;
+        }
+        self.halting = Some(code);
+        for &p in &self.peers {
+            force(&self.bus,
+                  Message::Halt(Halt {
+                      from: self.bus.id,
+                      to: p,
+                      code: code,
+                  }));
+        }
+    }
+
+    pub fn shutdown(&mut self) -> u8 {
+        // live tasks finalize, the outbox gets its last tries, then the selectors close
+        let x = into_raw(self);
+        for s in 0..self.tasks.len() {
+            if let Some(ref mut t) = from_raw(x).tasks[s] {
+                t.0.finalize();
+            }
+        }
+        // the bus is read on until it is quiet, so the Halts for the cores behind this one are passed on
+        let start = Instant::now();
+        let mut quiet = Instant::now();
+        while start.elapsed() < Duration::from_millis(STOP) {
+            if self.poll_bus() > 0 || !self.bus.flush() {
+                quiet = Instant::now();
+            } else if quiet.elapsed() >= Duration::from_millis(DRAIN) {
+                break;
+            }
+            thread::yield_now();
+        }
+        self.io.finalize();
+        self.halting.unwrap_or(0)
+    }
+
     pub fn mem(&mut self) -> UnsafeShared<Memory> {
         unsafe { UnsafeShared::new(&mut self.queues as *mut Memory) }
     }
 
-    pub fn run0(&mut self, input: Option<&'a str>) {
-        println!("BSP core {:?}", self.bus.id);
+    fn placement(&self) -> String {
+        match self.place {
+            Some((cpu, node)) => {
+                format!("on cpu {} node {}, bus {}",
+                        cpu,
+                        node,
+                        if self.bound { "bound" } else { "not bound" })
+            }
+            None => "unpinned".to_string(),
+        }
+    }
+
+    pub fn run0(&mut self, input: Option<&'a str>) -> u8 {
+        // returns the exit code once every core has stopped
+        println!("BSP core {:?} {}", self.bus.id, self.placement());
         self.io.spawn(Selector::Rx(Console::new()));
+        let sig = match Signals::new() {
+            Some(s) => Some(self.io.spawn(Selector::Sig(s))),
+            None => {
+                sys::catch_signals();
+                None
+            }
+        };
         let x = into_raw(self);
         let shell = from_raw(x).spawn(Job::Cps(CpsTask::new(self.mem())),
                                       Termination::Corecursive,
-                                      input);
+                                      input)
+            .expect("Scheduler: no slot for the shell.");
 
         self.handle_shell(input, shell);
 
-        loop {
-            self.poll_bus();
+        while self.halting.is_none() {
+            self.dead.clear();
+            let mut work = self.poll_bus();
             match from_raw(x).io.poll() {
-                Async::Ready((_, buf)) => self.handle_shell(from_raw(x).io.cmd(buf), shell),
+                Async::Ready((slot, buf)) => {
+                    work += 1;
+                    from_raw(x).stats.io += 1;
+                    if Some(slot) == sig {
+                        for &n in buf {
+                            self.signal(shell, n as usize);
+                        }
+                    } else {
+                        self.handle_shell(from_raw(x).io.cmd(buf), shell)
+                    }
+                }
                 _ => (),
             }
-            self.hibernate();
-            self.poll_tasks();
+            if !self.io.running() {
+                self.halt(0); // Ctrl-D
+            }
+            if let Some(n) = sys::caught() {
+                self.signal(shell, n);
+            }
+            work += self.poll_timers();
+            work += self.poll_signals(shell);
+            work += self.poll_downs();
+            work += self.poll_backlog();
+            work += self.poll_tasks();
+            work += self.poll_dump();
+            self.hibernate(work);
+        }
+        let code = self.shutdown();
+        self.join();
+        code
+    }
+
+    fn join(&mut self) {
+        // the AP cores that say they stopped are joined, one still running after STOP ms is left behind
+        let deadline = Instant::now() + Duration::from_millis(STOP);
+        while !self.threads.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let id = match self.stopped {
+                Some(ref acks) => {
+                    match acks.recv_timeout(deadline - now) {
+                        Ok(id) => id,
+                        Err(_) => break,
+                    }
+                }
+                None => break,
+            };
+            if let Some(k) = self.threads.iter().position(|t| t.0 == id) {
+                self.threads.remove(k).1.join().ok();
+            }
+        }
+        for (id, _) in self.threads.drain(..) {
+            println!("AP core {} did not stop", id);
         }
     }
 
     pub fn run(&mut self) {
-        println!("AP core {:?}", self.bus.id);
-        loop {
-            self.poll_bus();
-            self.hibernate();
-            self.poll_tasks();
+        println!("AP core {:?} {}", self.bus.id, self.placement());
+        let x = into_raw(self);
+        while self.halting.is_none() {
+            self.dead.clear();
+            let mut work = self.poll_bus();
+            from_raw(x).io.poll(); // only wakeups here
+            work += self.poll_timers();
+            work += self.poll_downs();
+            work += self.poll_backlog();
+            work += self.poll_tasks();
+            self.hibernate(work);
         }
+        self.shutdown();
     }
 }
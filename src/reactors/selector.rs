@@ -3,6 +3,7 @@ use io::token::Token;
 use reactors::system::IO;
 use reactors::ws::WsServer;
 use reactors::console::Console;
+use reactors::signals::Signals;
 use std::fmt::Arguments;
 
 const EVENTS_CAPACITY: usize = 1024;
@@ -18,6 +19,7 @@ pub trait Select<'a>: Write {
 pub enum Selector {
     Ws(WsServer),
     Rx(Console),
+    Sig(Signals),
 }
 
 impl Selector {
@@ -25,6 +27,7 @@ impl Selector {
         match *self {
             Selector::Ws(ref mut w) => w,
             Selector::Rx(ref mut c) => c,
+            Selector::Sig(ref mut s) => s,
         }
     }
 
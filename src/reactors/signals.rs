@@ -0,0 +1,70 @@
+// SIGHUP, SIGINT and SIGTERM read from a signalfd on the BSP, one byte per signal.
+// The scheduler hands them to .z.sig in the shell, see Scheduler::signal.
+
+use std::io::{self, Write};
+use std::os::unix::io::RawFd;
+use io::token::Token;
+use io::ready::Ready;
+use io::poll::*;
+use io::options::*;
+use io::event::Evented;
+use io::unix::stdio::EventedFd;
+use reactors::selector::{Select, Slot};
+use reactors::system::IO;
+use libc;
+use sys;
+
+pub struct Signals {
+    fd: RawFd,
+}
+
+impl Signals {
+    pub fn new() -> Option<Self> {
+        // None where there is no signalfd
+        sys::signal_fd().map(|fd| Signals { fd: fd })
+    }
+}
+
+impl Evented for Signals {
+    fn register(&self, poll: &Poll, token: Token, interest: Ready, opts: PollOpt) -> io::Result<()> {
+        EventedFd(&self.fd).register(poll, token, interest, opts)
+    }
+
+    fn reregister(&self, poll: &Poll, token: Token, interest: Ready, opts: PollOpt) -> io::Result<()> {
+        EventedFd(&self.fd).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &Poll) -> io::Result<()> {
+        EventedFd(&self.fd).deregister(poll)
+    }
+}
+
+impl<'a> Select<'a> for Signals {
+    fn init(&mut self, io: &mut IO, s: Slot) {
+        io.register(self, s);
+    }
+
+    fn select(&'a mut self, _: &'a mut IO, t: Token, buf: &mut [u8]) -> usize {
+        let sigs = sys::read_signals(self.fd);
+        let n = sigs.len().min(buf.len());
+        for k in 0..n {
+            buf[k] = sigs[k] as u8;
+        }
+        n
+    }
+
+    fn finalize(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+impl Write for Signals {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len()) // nothing to say to a signal
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
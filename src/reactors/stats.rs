@@ -0,0 +1,26 @@
+// What a core has been doing since it started, read by stats[] and dumped
+// to websocket clients as one JSON object per core.
+
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone)]
+pub struct Stats {
+    pub spawned: u64,
+    pub steps: u64, // task polls
+    pub messages: u64, // read from the bus
+    pub io: u64, // selector events
+    pub idle: Duration, // spinning or parked
+}
+
+pub fn millis(d: Duration) -> u64 {
+    d.as_secs() * 1000 + (d.subsec_nanos() / 1_000_000) as u64
+}
+
+pub fn json(core: usize, rows: &[(String, u64)]) -> String {
+    let mut s = format!("{{\"core\":{}", core);
+    for &(ref k, v) in rows {
+        s.push_str(&format!(",\"{}\":{}", k, v));
+    }
+    s.push('}');
+    s
+}
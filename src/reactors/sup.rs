@@ -0,0 +1,128 @@
+// Supervisors restart crashed children of an O task, Erlang style.
+// A supervisor belongs to the task that made it and stops with it. When children
+// crash more than `intensity` times within `period` it gives up and crashes its
+// owner, so supervisors nest through the tasks that own them.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const NOT_STARTED: usize = !0; // the pid in the Down of a child its core had no slot for
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    OneForOne, // only the crashed child restarts
+    OneForAll, // every child restarts
+    RestForOne, // the crashed child and the ones started after it
+}
+
+impl Strategy {
+    pub fn from_name(s: &str) -> Option<Strategy> {
+        match s {
+            "oneforone" => Some(Strategy::OneForOne),
+            "oneforall" => Some(Strategy::OneForAll),
+            "restforone" => Some(Strategy::RestForOne),
+            _ => None,
+        }
+    }
+
+    pub fn from_u8(x: u8) -> Option<Strategy> {
+        match x {
+            0 => Some(Strategy::OneForOne),
+            1 => Some(Strategy::OneForAll),
+            2 => Some(Strategy::RestForOne),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Child {
+    pub core: usize,
+    pub prio: u8,
+    pub txt: String,
+    pub pid: Option<usize>, // None while starting or after a normal exit
+    pub gen: usize, // bumped by every start, older instances are stopped
+}
+
+#[derive(Debug)]
+pub struct Supervisor {
+    pub owner: usize,
+    pub strategy: Strategy,
+    pub intensity: usize,
+    pub period: Duration,
+    pub children: Vec<Child>,
+    restarts: VecDeque<Instant>,
+}
+
+impl Supervisor {
+    pub fn new(owner: usize, strategy: Strategy, intensity: usize, period: Duration) -> Self {
+        Supervisor {
+            owner: owner,
+            strategy: strategy,
+            intensity: intensity,
+            period: period,
+            children: vec![],
+            restarts: VecDeque::new(),
+        }
+    }
+
+    pub fn add(&mut self, core: usize, prio: u8, txt: String) -> usize {
+        self.children.push(Child {
+            core: core,
+            prio: prio,
+            txt: txt,
+            pid: None,
+            gen: 0,
+        });
+        self.children.len() - 1
+    }
+
+    pub fn started(&mut self, child: usize, gen: usize, pid: usize) -> bool {
+        // false for an instance a later start made obsolete
+        match self.children.get_mut(child) {
+            Some(c) => {
+                if c.gen != gen {
+                    return false;
+                }
+                c.pid = Some(pid);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn exited(&mut self, child: usize, pid: usize) {
+        if let Some(c) = self.children.get_mut(child) {
+            if c.pid == Some(pid) {
+                c.pid = None;
+            }
+        }
+    }
+
+    pub fn crashed(&mut self, child: usize, pid: usize) -> Option<Vec<usize>> {
+        // the children to restart, None once the restart intensity is exceeded
+        match self.children.get_mut(child) {
+            Some(c) => {
+                if c.pid != Some(pid) && !(pid == NOT_STARTED && c.pid.is_none()) {
+                    return Some(vec![]); // an instance we stopped ourselves
+                }
+                c.pid = None;
+            }
+            None => return Some(vec![]),
+        }
+        let now = Instant::now();
+        self.restarts.push_back(now);
+        while self.restarts.front().map_or(false, |&t| now.duration_since(t) > self.period) {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() > self.intensity {
+            return None;
+        }
+        let n = self.children.len();
+        Some(match self.strategy {
+            Strategy::OneForOne => vec![child],
+            Strategy::OneForAll => (0..n).collect(),
+            Strategy::RestForOne => (child..n).collect(),
+        })
+    }
+}
@@ -5,6 +5,7 @@ use io::ready::Ready;
 use io::options::PollOpt;
 use io::event::Evented;
 use reactors::selector::{Slot, Selector};
+use timers::timer::{Timer, Timeout, Builder};
 use std::time::Duration;
 use std::str::from_utf8;
 use handle;
@@ -12,6 +13,8 @@ use handle;
 const EVENTS_CAPACITY: usize = 1024;
 const SUBSCRIBERS_CAPACITY: usize = 16;
 const BUFFER_CAPACITY: usize = 1024;
+const TIMER_TICK: u64 = 10; // ms
+const WAKEUP: Slot = Slot(!0); // tokens that only wake the poll up, no selector behind
 
 #[derive(Debug)]
 pub enum Async<T> {
@@ -28,6 +31,8 @@ pub struct IO {
     running: bool,
     buf: [u8; BUFFER_CAPACITY],
     polled: usize,
+    timer: Timer<usize>,
+    clock: Option<Token>, // the timer's token, taken when the first timeout is set
 }
 
 impl<'a> IO {
@@ -41,6 +46,8 @@ impl<'a> IO {
             running: true,
             buf: [0u8; BUFFER_CAPACITY],
             polled: 0,
+            timer: Builder::default().tick_duration(Duration::from_millis(TIMER_TICK)).build(),
+            clock: None,
         }
     }
 
@@ -54,6 +61,27 @@ impl<'a> IO {
         Token(t)
     }
 
+    pub fn set_timeout(&mut self, after: Duration, id: usize) -> Option<Timeout> {
+        // the timer wakes up the poll, its thread only starts with the first timeout
+        if self.clock.is_none() {
+            let (s1, s2) = handle::split(self);
+            s1.clock = Some(s2.register(&s1.timer, WAKEUP));
+        }
+        self.timer.set_timeout(after, id).ok()
+    }
+
+    pub fn cancel_timeout(&mut self, t: &Timeout) -> Option<usize> {
+        self.timer.cancel_timeout(t)
+    }
+
+    pub fn timeouts(&mut self) -> Vec<usize> {
+        let mut v = vec![];
+        while let Some(id) = self.timer.poll() {
+            v.push(id);
+        }
+        v
+    }
+
     pub fn spawn(&mut self, s: Selector) -> Slot {
         let (s1, s2) = handle::split(self);
         s1.selectors.push(s);
@@ -74,14 +102,29 @@ impl<'a> IO {
         Ok(())
     }
 
-    #[inline]
-    fn poll_if_need(&mut self) {
+    pub fn wakeup<E>(&mut self, e: &E) -> Token
+        where E: Evented
+    {
+        self.register(e, WAKEUP)
+    }
+
+    pub fn park(&mut self, timeout: Duration) {
+        // sleeps until an event or the timeout, events not read yet keep it awake
         if self.polled == 0 {
-            self.poll.poll(&mut self.events, Some(Duration::from_millis(100))).expect("No events in poll.");
-            self.polled = self.events.len();
+            // a signal cuts the wait short, that is no error
+            self.polled = match self.poll.poll(&mut self.events, Some(timeout)) {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => 0,
+                Err(e) => panic!("No events in poll: {:?}", e),
+            };
         }
     }
 
+    #[inline]
+    fn poll_if_need(&mut self) {
+        self.park(Duration::from_millis(0))
+    }
+
     pub fn cmd(&mut self, buf: &'a [u8]) -> Option<&'a str> {
         if buf.len() == 0 {
             return None;
@@ -98,25 +141,34 @@ impl<'a> IO {
 
     pub fn poll(&'a mut self) -> Async<(Slot, &'a [u8])> {
         self.poll_if_need();
-        match self.polled {
-            0 => Async::NotReady,
-            id => {
-                self.polled -= 1;
-                let e = self.events.get(self.polled).expect("Can't retrieve an event.");
-                let (s1, s2) = handle::split(self);
-                let buf = &mut s1.buf;
-                let slot = s1.slots.get(e.token().0).expect("Can't retrieve a slot.");
-                let sel = s1.selectors.get_mut(slot.0).expect("Can't retrieve a selector.");
-                match sel.unpack().select(s2, e.token(), buf) {
-                    0 => Async::NotReady,
-                    x => Async::Ready((Slot(slot.0), &buf[..x])),
-                }
+        while self.polled > 0 {
+            self.polled -= 1;
+            let e = self.events.get(self.polled).expect("Can't retrieve an event.");
+            let (s1, s2) = handle::split(self);
+            let buf = &mut s1.buf;
+            let slot = s1.slots.get(e.token().0).expect("Can't retrieve a slot.");
+            if *slot == WAKEUP {
+                continue;
+            }
+            let sel = s1.selectors.get_mut(slot.0).expect("Can't retrieve a selector.");
+            match sel.unpack().select(s2, e.token(), buf) {
+                0 => return Async::NotReady,
+                x => return Async::Ready((Slot(slot.0), &buf[..x])),
             }
         }
+        Async::NotReady
     }
 
-    #[inline]
-    fn finalize(&mut self) {
+    pub fn stop(&mut self) {
+        // the console is closed, the BSP halts
+        self.running = false;
+    }
+
+    pub fn running(&self) -> bool {
+        self.running
+    }
+
+    pub fn finalize(&mut self) {
         for s in self.selectors.iter_mut() {
             s.unpack().finalize();
         }
@@ -11,8 +11,27 @@ pub enum Termination {
     Corecursive,
 }
 
+#[derive(Debug,PartialEq,Clone,Copy)]
+pub enum State {
+    Runnable,
+    Queue, // blocked in rcv
+    Ack, // an intercore request is in flight
+    Sleeping, // nothing to evaluate until Exec or a timer
+}
+
+impl State {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            State::Runnable => "runnable",
+            State::Queue => "queue",
+            State::Ack => "ack",
+            State::Sleeping => "sleeping",
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct T3<T>(pub T, pub Termination);
+pub struct T3<T>(pub T, pub Termination, pub u8); // task, termination, priority
 
 #[derive(Debug,Clone)]
 pub enum Poll<T, E> {
@@ -35,6 +54,8 @@ pub enum Context<'a> {
     NodeAck(usize, usize),
     Intercore(&'a Message),
     Init(usize),
+    Block, // the builtin can't complete yet, the call is retried on resume
+    Fail(usize, String), // an intercore request of the task was refused
     Nil,
 }
 
@@ -172,9 +172,21 @@ impl WsServer {
 
     pub fn write_to_clients(&mut self, payload: &[u8]) {
         let sz = payload.len();
-        let mut buf = Vec::<u8>::with_capacity(sz + 2);
+        let mut buf = Vec::<u8>::with_capacity(sz + 10);
         buf.push(130);
-        buf.push(sz as u8);
+        // 7 bit length, or 126 and 16 bits, or 127 and 64 bits
+        if sz < 126 {
+            buf.push(sz as u8);
+        } else if sz <= 0xffff {
+            buf.push(126);
+            buf.push((sz >> 8) as u8);
+            buf.push(sz as u8);
+        } else {
+            buf.push(127);
+            for i in (0..8).rev() {
+                buf.push((sz >> (8 * i)) as u8);
+            }
+        }
         buf.extend_from_slice(payload);
         for c in self.clients.iter_mut().filter(|c| c.1.ready) {
             c.1.sock.write(&buf);
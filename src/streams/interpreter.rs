@@ -9,6 +9,7 @@ use reactors::task::Context;
 use intercore::message::Message;
 use reactors::scheduler::Scheduler;
 use handle::{self, into_raw, from_raw, UnsafeShared};
+use std::time::Instant;
 
 const PREEMPTION: u64 = 20000000; // Yield each two instructions
 
@@ -18,12 +19,12 @@ pub enum Cont<'a> {
     Assign(&'a AST<'a>, &'a Cont<'a>),
     Cond(&'a AST<'a>, &'a AST<'a>, &'a Cont<'a>),
     Func(&'a AST<'a>, &'a AST<'a>, &'a AST<'a>, &'a Cont<'a>),
-    List(&'a AST<'a>, ASTIter<'a>, &'a Cont<'a>),
+    List(&'a Cont<'a>),
     Dict(ASTAcc<'a>, ASTIter<'a>, &'a Cont<'a>),
-    DictComplete(ASTAcc<'a>, ASTIter<'a>, usize, &'a Cont<'a>),
     Call(&'a AST<'a>, &'a Cont<'a>),
     Verb(Verb, &'a AST<'a>, u8, &'a Cont<'a>),
     Adverb(Adverb, &'a AST<'a>, &'a Cont<'a>),
+    Apply(&'a AST<'a>, &'a Cont<'a>),
     Return,
     Intercore(Message, &'a Cont<'a>),
     Yield(&'a Cont<'a>),
@@ -45,6 +46,9 @@ pub struct Interpreter<'a> {
     pub registers: Lazy<'a>,
     pub counter: u64,
     pub task_id: usize,
+    pub core: usize,
+    pub corr: usize, // id of the last intercore request, replies carry it back
+    pub blocked: Option<(usize, usize, Option<Instant>)>, // subscriber, count and deadline rcv waits on
 }
 
 impl<'a> Interpreter<'a> {
@@ -58,6 +62,9 @@ impl<'a> Interpreter<'a> {
             edge: Message::Nop,
             registers: Lazy::Start,
             task_id: 0,
+            core: 0,
+            corr: 0,
+            blocked: None,
             counter: 1,
         };
         Ok(interpreter)
@@ -71,12 +78,48 @@ impl<'a> Interpreter<'a> {
         let snd = s1.arena.intern_ast("snd".to_string());
         let rcv = s1.arena.intern_ast("rcv".to_string());
         let spawn = s1.arena.intern_ast("spawn".to_string());
+        let wait = s1.arena.intern_ast("wait".to_string());
+        let call = s1.arena.intern_ast("call".to_string());
+        let trcv = s1.arena.intern_ast("trcv".to_string());
+        let rcvall = s1.arena.intern_ast("rcvall".to_string());
+        let pubs = s1.arena.intern_ast("pubs".to_string());
+        let after = s1.arena.intern_ast("after".to_string());
+        let every = s1.arena.intern_ast("every".to_string());
+        let cancel = s1.arena.intern_ast("cancel".to_string());
+        let qos = s1.arena.intern_ast("qos".to_string());
+        let kill = s1.arena.intern_ast("kill".to_string());
+        let tasks = s1.arena.intern_ast("tasks".to_string());
+        let sup = s1.arena.intern_ast("sup".to_string());
+        let child = s1.arena.intern_ast("child".to_string());
+        let link = s1.arena.intern_ast("link".to_string());
+        let monitor = s1.arena.intern_ast("monitor".to_string());
+        let stats = s1.arena.intern_ast("stats".to_string());
+        let exit = s1.arena.intern_ast("exit".to_string());
+        let rcvn = s1.arena.intern_ast("rcvn".to_string());
         s1.env.define(ast::extract_name(&print), print);
         s1.env.define(ast::extract_name(&publ), publ);
         s1.env.define(ast::extract_name(&subs), subs);
         s1.env.define(ast::extract_name(&snd), snd);
         s1.env.define(ast::extract_name(&rcv), rcv);
         s1.env.define(ast::extract_name(&spawn), spawn);
+        s1.env.define(ast::extract_name(&wait), wait);
+        s1.env.define(ast::extract_name(&call), call);
+        s1.env.define(ast::extract_name(&trcv), trcv);
+        s1.env.define(ast::extract_name(&rcvall), rcvall);
+        s1.env.define(ast::extract_name(&pubs), pubs);
+        s1.env.define(ast::extract_name(&after), after);
+        s1.env.define(ast::extract_name(&every), every);
+        s1.env.define(ast::extract_name(&cancel), cancel);
+        s1.env.define(ast::extract_name(&qos), qos);
+        s1.env.define(ast::extract_name(&kill), kill);
+        s1.env.define(ast::extract_name(&tasks), tasks);
+        s1.env.define(ast::extract_name(&sup), sup);
+        s1.env.define(ast::extract_name(&child), child);
+        s1.env.define(ast::extract_name(&link), link);
+        s1.env.define(ast::extract_name(&monitor), monitor);
+        s1.env.define(ast::extract_name(&stats), stats);
+        s1.env.define(ast::extract_name(&exit), exit);
+        s1.env.define(ast::extract_name(&rcvn), rcvn);
         let x = unsafe { &mut *s1.arena.asts.get() };
         s2.arena.builtins = x.len() as u16;
     }
@@ -119,6 +162,68 @@ impl<'a> Interpreter<'a> {
             Context::NodeAck(_, value) => {
                 ret = from_raw(h).arena.ast(AST::Atom(Atom::Value(Value::Number(value as i64))));
             }
+            Context::Cont(_, &Message::AckSpawn(ref a)) => {
+                // remote task handle is (core;pid)
+                ret = from_raw(h).arena.ast(AST::Atom(Atom::Value(Value::VecInt(vec![a.from as i64, a.pid as i64]))));
+            }
+            Context::Cont(_, &Message::AckJoin(ref a)) => {
+                ret = from_raw(h).arena.ast(AST::Atom(Atom::Value(a.value.clone())));
+            }
+            Context::Cont(_, &Message::AckCall(ref a)) => {
+                ret = from_raw(h).arena.ast(AST::Atom(Atom::Value(a.value.clone())));
+            }
+            Context::Cont(_, &Message::AckNames(ref a)) => {
+                // one (`name;core;id) row per registered publisher
+                let arena = &from_raw(h).arena;
+                let rows = a.names
+                    .iter()
+                    .map(|&(ref name, core, id)| {
+                        let s = match arena.intern_symbol(name.clone()) {
+                            AST::Atom(Atom::Value(v)) => v,
+                            _ => Value::Nil,
+                        };
+                        Value::List(vec![s, Value::Number(core as i64), Value::Number(id as i64)])
+                    })
+                    .collect();
+                ret = arena.ast(AST::Atom(Atom::Value(Value::List(rows))));
+            }
+            Context::Cont(_, &Message::AckMetrics(ref a)) => {
+                // one (`counter;value) row per counter
+                let arena = &from_raw(h).arena;
+                let rows = a.stats
+                    .iter()
+                    .map(|&(ref name, v)| {
+                        let s = match arena.intern_symbol(name.clone()) {
+                            AST::Atom(Atom::Value(v)) => v,
+                            _ => Value::Nil,
+                        };
+                        Value::List(vec![s, Value::Number(v as i64)])
+                    })
+                    .collect();
+                ret = arena.ast(AST::Atom(Atom::Value(Value::List(rows))));
+            }
+            Context::Cont(_, &Message::AckTasks(ref a)) => {
+                // one (pid;`state;priority) row per live task
+                let arena = &from_raw(h).arena;
+                let rows = a.tasks
+                    .iter()
+                    .map(|&(pid, ref state, prio)| {
+                        let s = match arena.intern_symbol(state.clone()) {
+                            AST::Atom(Atom::Value(v)) => v,
+                            _ => Value::Nil,
+                        };
+                        Value::List(vec![Value::Number(pid as i64), s, Value::Number(prio as i64)])
+                    })
+                    .collect();
+                ret = arena.ast(AST::Atom(Atom::Value(Value::List(rows))));
+            }
+            Context::Fail(_, ref e) => {
+                from_raw(h).registers = Lazy::Start;
+                return Err(Error::EvalError {
+                    desc: e.clone(),
+                    ast: format!("{}", ast),
+                });
+            }
             _ => (),
         }
 
@@ -144,7 +249,10 @@ impl<'a> Interpreter<'a> {
                 }
                 Lazy::Start => break,
                 Lazy::Continuation(node, ast, cont) => {
-                    let ast_ = from_raw(h).arena.ast(AST::Atom(Atom::Yield(Context::Intercore(&from_raw(h).edge))));
+                    let ast_ = match from_raw(h).edge {
+                        Message::Nop => from_raw(h).arena.ast(AST::Atom(Atom::Yield(Context::Nil))),
+                        _ => from_raw(h).arena.ast(AST::Atom(Atom::Yield(Context::Intercore(&from_raw(h).edge)))),
+                    };
                     from_raw(h).registers = Lazy::Defer(node, ast, cont);
                     from_raw(h).counter = counter + 1;
                     return Ok(ast_);
@@ -178,6 +286,17 @@ impl<'a> Interpreter<'a> {
         })
     }
 
+    pub fn ready(&self) -> bool {
+        // a task blocked in rcv sleeps until its subscriber has data or the deadline passes
+        match self.blocked {
+            Some((s, n, deadline)) => {
+                self.queues.subscribers().get(s).map_or(true, |x| x.ready(n)) ||
+                deadline.map_or(false, |d| Instant::now() >= d)
+            }
+            None => true,
+        }
+    }
+
     pub fn gc(&self) -> usize {
         self.env.clean() + self.arena.clean()
     }
@@ -193,7 +312,7 @@ impl<'a> Interpreter<'a> {
                                val,
                                from_raw(h).arena.cont(Cont::Cond(left, right, cont))))
             }
-            &AST::Atom(Atom::List(x)) => from_raw(h).defer_dict(node, x, cont), // so far list are treated the same as dicts
+            &AST::Atom(Atom::List(x)) => from_raw(h).defer_dict(node, x, from_raw(h).arena.cont(Cont::List(cont))),
             &AST::Atom(Atom::Dict(x)) => from_raw(h).defer_dict(node, x, cont),
             &AST::Atom(Atom::Call(c, a)) => {
                 // println!("Defer call: {:?} {:?}", c, a);
@@ -202,6 +321,15 @@ impl<'a> Interpreter<'a> {
             &AST::Atom(Atom::Verb(ref verb, left, right)) => {
                 // println!("Defer Verb: {:?} {:?}", left, right);
                 match (left, right) {
+                    (&AST::Atom(Atom::Value(Value::Nil)), &AST::Atom(Atom::Dict(_))) => {
+                        // +[x;y] calls the verb with dict as arguments
+                        let f = from_raw(h).arena.ast(AST::Atom(Atom::Verb(verb.clone(), left, left)));
+                        Ok(Lazy::Defer(node, right, from_raw(h).arena.cont(Cont::Call(f, cont))))
+                    }
+                    (_, &AST::Atom(Atom::Value(Value::Nil))) => {
+                        // bare verb + and left section 2* are projections
+                        from_raw(h).run_cont(node, from_raw(h).verb_lambda(node, verb.clone(), left, right), cont)
+                    }
                     (&AST::Atom(Atom::Value(_)), _) => {
                         Ok(Lazy::Defer(node,
                                        right,
@@ -230,6 +358,9 @@ impl<'a> Interpreter<'a> {
                     Err(x) => Err(x),
                 }
             }
+            &AST::Atom(Atom::Adverb(adv, f, x)) => {
+                Ok(Lazy::Defer(node, x, from_raw(h).arena.cont(Cont::Adverb(adv, f, cont))))
+            }
             &AST::Atom(Atom::Lambda(_, x, y)) => {
                 from_raw(h).run_cont(node,
                                      from_raw(h).arena.ast(AST::Atom(Atom::Lambda(Some(node), x, y))),
@@ -264,6 +395,10 @@ impl<'a> Interpreter<'a> {
                         -> Result<Lazy<'a>, Error> {
         // println!("Eval Fun: {:?}", fun);
         let h = into_raw(self);
+        if from_raw(h).is_fun(args) {
+            // f g is a composition when g is a function
+            return from_raw(h).run_cont(node, from_raw(h).compose(node, fun, args), cont);
+        }
         match fun {
             &AST::Atom(Atom::Lambda(closure, names, body)) => {
                 self.run_cont(if closure == None {
@@ -281,10 +416,20 @@ impl<'a> Interpreter<'a> {
                     Ok((c, f)) => {
                         match c {
                             &AST::Atom(Atom::NameInt(n)) if n < from_raw(h).arena.builtins => {
-                                eval_context(f,
-                                             from_raw(h),
-                                             internals(from_raw(h), n, args, &from_raw(h).arena),
-                                             cont)
+                                if let Some(p) = from_raw(h).project(f, c, args) {
+                                    return from_raw(h).run_cont(f, p, cont);
+                                }
+                                match internals(from_raw(h), n, args, &from_raw(h).arena) {
+                                    Context::Block => {
+                                        let a = match args {
+                                            &AST::Vector(_) => from_raw(h).arena.ast(AST::Atom(Atom::Dict(args))),
+                                            x => x,
+                                        };
+                                        let retry = from_raw(h).arena.ast(AST::Atom(Atom::Call(c, a)));
+                                        Ok(Lazy::Continuation(node, retry, cont))
+                                    }
+                                    x => eval_context(f, from_raw(h), x, cont),
+                                }
                             }
                             _ => from_raw(h).evaluate_fun(f, c, args, cont),
                         }
@@ -292,6 +437,16 @@ impl<'a> Interpreter<'a> {
                     Err(x) => Err(x),
                 }
             }
+            &AST::Atom(Atom::Verb(verb, l, r)) => {
+                let f = from_raw(h).verb_lambda(node, verb, l, r);
+                from_raw(h).evaluate_fun(node, f, args, cont)
+            }
+            &AST::Atom(Atom::Call(..)) |
+            &AST::Atom(Atom::Cond(..)) |
+            &AST::Atom(Atom::Adverb(..)) => {
+                // callee is an expression like snd[p;], evaluate it first
+                Ok(Lazy::Defer(node, fun, from_raw(h).arena.cont(Cont::Apply(args, cont))))
+            }
             x => {
                 Err(Error::EvalError {
                     desc: "Call Error".to_string(),
@@ -301,6 +456,103 @@ impl<'a> Interpreter<'a> {
         }
     }
 
+    fn is_fun(&self, a: &'a AST<'a>) -> bool {
+        match a {
+            &AST::Atom(Atom::Lambda(..)) |
+            &AST::Atom(Atom::Verb(..)) => true,
+            &AST::Atom(Atom::NameInt(n)) => n < self.arena.builtins,
+            _ => false,
+        }
+    }
+
+    fn hole(&'a self, i: usize) -> &'a AST<'a> {
+        // names that can not be written in O, so they never shadow user ones
+        self.arena.intern_ast(format!("_{}", i))
+    }
+
+    fn verb_lambda(&'a self, node: otree::NodeId, verb: Verb, l: &'a AST<'a>, r: &'a AST<'a>) -> &'a AST<'a> {
+        // + => {[_0;_1]_0+_1}, 2* => {[_1]2*_1}, *2 => {[_0]_0*2}
+        let nil = |a: &'a AST<'a>| match a {
+            &AST::Atom(Atom::Value(Value::Nil)) => true,
+            _ => false,
+        };
+        let (x, y) = (self.hole(0), self.hole(1));
+        let (names, l, r) = match (nil(l), nil(r)) {
+            (true, true) => (self.arena.vec(vec![x.clone(), y.clone()]), x, y),
+            (true, false) => (x, x, r),
+            _ => (y, l, y),
+        };
+        let body = self.arena.vec(vec![AST::Atom(Atom::Verb(verb, l, r))]);
+        self.arena.ast(AST::Atom(Atom::Lambda(Some(node), names, body)))
+    }
+
+    fn project(&'a self, node: otree::NodeId, f: &'a AST<'a>, args: &'a AST<'a>) -> Option<&'a AST<'a>> {
+        // snd[p;] => {[_1]snd[p;_1]}
+        let v = match args {
+            &AST::Vector(ref v) if v.len() > 1 && v.iter().any(|x| *x == AST::Atom(Atom::Any)) => v,
+            _ => return None,
+        };
+        let mut names = vec![];
+        let mut filled = vec![];
+        for (i, a) in v.iter().enumerate() {
+            match a {
+                &AST::Atom(Atom::Any) => {
+                    let n = self.hole(i);
+                    names.push(n.clone());
+                    filled.push(n.clone());
+                }
+                x => filled.push(x.clone()),
+            }
+        }
+        let call = AST::Atom(Atom::Call(f, self.arena.ast(AST::Atom(Atom::Dict(self.arena.vec(filled))))));
+        Some(self.arena.ast(AST::Atom(Atom::Lambda(Some(node), self.arena.vec(names), self.arena.vec(vec![call])))))
+    }
+
+    fn compose(&'a self, node: otree::NodeId, f: &'a AST<'a>, g: &'a AST<'a>) -> &'a AST<'a> {
+        // f g => {[_0]f[g[_0]]}
+        let x = self.hole(0);
+        let inner = self.arena.ast(AST::Atom(Atom::Call(g, self.arena.ast(AST::Atom(Atom::Dict(self.arena.vec(vec![x.clone()])))))));
+        let call = AST::Atom(Atom::Call(f, self.arena.ast(AST::Atom(Atom::Dict(self.arena.vec(vec![inner.clone()]))))));
+        self.arena.ast(AST::Atom(Atom::Lambda(Some(node), x, self.arena.vec(vec![call]))))
+    }
+
+    fn adverb(&'a self, adv: Adverb, f: &'a AST<'a>, val: &'a AST<'a>) -> Result<&'a AST<'a>, Error> {
+        // f'x => (f[x0];f[x1];..), f/x => f[f[x0;x1];x2]..
+        let items: Vec<AST<'a>> = match val {
+            &AST::Atom(Atom::Value(ref v)) => {
+                match v.items() {
+                    Some(x) => x.into_iter().map(|x| AST::Atom(Atom::Value(x))).collect(),
+                    None => vec![val.clone()],
+                }
+            }
+            &AST::Vector(ref v) => v.clone(),
+            x => vec![x.clone()],
+        };
+        let call = |args: Vec<AST<'a>>| {
+            AST::Atom(Atom::Call(f, self.arena.ast(AST::Atom(Atom::Dict(self.arena.vec(args))))))
+        };
+        match adv {
+            Adverb::Each => {
+                let calls = items.into_iter().map(|x| call(vec![x])).collect();
+                Ok(self.arena.ast(AST::Atom(Atom::List(self.arena.vec(calls)))))
+            }
+            Adverb::Over => {
+                let mut it = items.into_iter();
+                let first = match it.next() {
+                    Some(x) => x,
+                    None => return Ok(self.arena.nil()),
+                };
+                Ok(self.arena.ast(it.fold(first, |acc, x| call(vec![acc, x]))))
+            }
+            x => {
+                Err(Error::EvalError {
+                    desc: "Adverb is not implemented".to_string(),
+                    ast: format!("{:?}", x),
+                })
+            }
+        }
+    }
+
     pub fn evaluate_expr(&'a self,
                          node: otree::NodeId,
                          exprs: &'a AST<'a>,
@@ -312,20 +564,38 @@ impl<'a> Interpreter<'a> {
                        self.arena.cont(Cont::Expressions(exprs, None, cont))))
     }
 
-    pub fn defer_dict(&'a self, node: otree::NodeId, dict: &'a AST<'a>, cont: &'a Cont<'a>) -> Result<Lazy<'a>, Error> {
+    pub fn defer_dict(&'a mut self, node: otree::NodeId, dict: &'a AST<'a>, cont: &'a Cont<'a>) -> Result<Lazy<'a>, Error> {
 
         // println!("defer Dict: {:?}", dict);
         match dict {
             &AST::Vector(ref v) => {
                 // create new accumulator and start calculating dict values
-                Ok(Lazy::Defer(node,
-                               dict,
-                               self.arena.cont(Cont::Dict(ASTAcc::new(), v.as_slice().iter(), cont))))
+                self.next_item(node, ASTAcc::new(), v.as_slice().iter(), cont)
             }
             x => Ok(Lazy::Defer(node, x, cont)),
         }
     }
 
+    fn next_item(&'a mut self,
+                 node: otree::NodeId,
+                 acc: ASTAcc<'a>,
+                 rest: ASTIter<'a>,
+                 cont: &'a Cont<'a>)
+                 -> Result<Lazy<'a>, Error> {
+        let h = into_raw(self);
+        let mut r = rest;
+        match r.next() {
+            Some(x) => {
+                // println!("run_cont dict defer: x={} #### cont: {:?}\n", x, cont);
+                Ok(Lazy::Defer(node, x, from_raw(h).arena.cont(Cont::Dict(acc, r, cont))))
+            }
+            _ => {
+                // entire vector calculated, time to move calculation on
+                from_raw(h).run_cont(node, from_raw(h).arena.ast(AST::Vector(acc.disown())), cont)
+            }
+        }
+    }
+
     pub fn run_cont(&'a mut self, node: otree::NodeId, val: &'a AST<'a>, con: &'a Cont<'a>) -> Result<Lazy<'a>, Error> {
         // println!("run_cont: val: {:?} #### cont: {:?}\n", val, cont);
         let h = into_raw(self);
@@ -349,11 +619,18 @@ impl<'a> Interpreter<'a> {
                 let f = from_raw(h).env.new_child(node);
                 let mut partial: Vec<AST> = Vec::new(); // vector of unfilled/empty names
 
-                for (k, v) in names.into_iter().zip(args.into_iter()) {
+                let args: &'a AST<'a> = match **args {
+                    // f[] is a call with nil, not a projection
+                    AST::Vector(ref v) if v.len() == 1 && v[0] == AST::Atom(Atom::Any) => from_raw(h).arena.nil(),
+                    AST::Atom(Atom::Any) => from_raw(h).arena.nil(),
+                    _ => *args,
+                };
+                let mut a = args.into_iter();
+                for k in names.into_iter() {
                     // println!("cont_func name={:?} arg={:?}", k, v);
-                    match v {
-                        &AST::Atom(Atom::Any) => partial.push(k.clone()),
-                        _ => {
+                    match a.next() {
+                        Some(&AST::Atom(Atom::Any)) | None => partial.push(k.clone()), // missing args project
+                        Some(v) => {
                             from_raw(h).env.define(ast::extract_name(k), v);
                         }
                     };
@@ -400,65 +677,45 @@ impl<'a> Interpreter<'a> {
 
                 }
             }
-            &Cont::DictComplete(ref acc, ref rest, idx, cont) => {
-                // println!("run_cont dict_complete: acc={} idx={} val={} #### cont: {:?}\n", acc, idx, val, cont);
-                acc.set(idx, val.clone()); // TODO Get rid of clone!!!
-
-                Ok(Lazy::Defer(node,
-                               val,
-                               from_raw(h).arena.cont(Cont::Dict(acc.clone(), rest.clone(), cont))))
+            &Cont::List(cont) => {
+                // evaluated items of (..) become a typed vector or a general list
+                let v = match (val, verb::value(val)) {
+                    (&AST::Vector(_), Some(x)) => {
+                        from_raw(h).arena.ast(AST::Atom(Atom::Value(match x.into_owned() {
+                            Value::List(items) => Value::pack(items),
+                            x => x,
+                        })))
+                    }
+                    _ => val,
+                };
+                from_raw(h).run_cont(node, v, cont)
+            }
+            &Cont::Adverb(adv, f, cont) => {
+                let a = try!(from_raw(h).adverb(adv, f, val));
+                Ok(Lazy::Defer(node, a, cont))
             }
+            &Cont::Apply(args, cont) => from_raw(h).evaluate_fun(node, val, args, cont),
             &Cont::Dict(ref acc, ref rest, cont) => {
                 // println!("run_cont dict: acc={} #### cont: {:?}\n", acc, cont);
-
-                match val {
-                    &AST::Atom(_) => {
-                        acc.push(val);
-                    }
-                    _ => {}
-                }
-
-                let mut r = rest.clone();
-                match r.next() {
-                    Some(v) => {
-                        // println!("run_cont dict next: x={} #### cont: {:?}\n", v, cont);
-                        match v {
-                            &AST::Atom(Atom::Dict(ref astv)) |
-                            &AST::Atom(Atom::List(ref astv)) // so far list are treated the same as dicts
-                                => {
-                                //println!("run_cont dict vec: val={} #### cont: {:?}\n", val, cont);
-                                let idx = acc.push( from_raw(h).arena.any()); // allocate empty place for vector result
-                                 from_raw(h).defer_dict(node, astv,
-                                                 from_raw(h).arena.cont(Cont::DictComplete(acc.clone(), r, idx, cont)))
-                            }
-                            x => {
-                                //println!("run_cont dict defer: x={} #### cont: {:?}\n", x, cont);
-                                Ok(Lazy::Defer(node,
-                                               x,
-                                                from_raw(h).arena.cont(Cont::Dict(acc.clone(), r, cont))))
-                            }
-                        }
-                    }
-                    _ => {
-                        // entire vector calculated, time to move calculation on
-                        from_raw(h).run_cont(node, from_raw(h).arena.ast(AST::Vector(acc.disown())), cont)
-                    }
-                }
+                acc.push(val);
+                from_raw(h).next_item(node, acc.clone(), rest.clone(), cont)
             }
             &Cont::Verb(ref verb, right, swap, cont) => {
                 // println!("Cont Verb: {:?}", val);
                 match (right, val) {
-                    (&AST::Atom(Atom::Value(_)), &AST::Atom(Atom::Value(_))) => {
-                        match swap {
-                            0 => {
-                                let a = verb::eval(verb.clone(), right, val).unwrap();
-                                from_raw(h).run_cont(node, from_raw(h).arena.ast(a), cont)
-                            }
-                            _ => {
-                                let a = verb::eval(verb.clone(), val, right).unwrap();
-                                from_raw(h).run_cont(node, from_raw(h).arena.ast(a), cont)
-                            }
-                        }
+                    (&AST::Atom(Atom::Value(_)), &AST::Atom(Atom::Value(_))) |
+                    (&AST::Atom(Atom::Value(_)), &AST::Vector(_)) |
+                    (&AST::Vector(_), &AST::Atom(Atom::Value(_))) |
+                    (&AST::Vector(_), &AST::Vector(_)) => {
+                        let (l, r) = match swap {
+                            0 => (right, val),
+                            _ => (val, right),
+                        };
+                        let a = match *verb {
+                            Verb::Cast => try!(verb::cast(&from_raw(h).arena, l, r)),
+                            _ => try!(verb::eval(verb.clone(), l, r)),
+                        };
+                        from_raw(h).run_cont(node, from_raw(h).arena.ast(a), cont)
                     }
                     (x, &AST::Atom(Atom::Value(Value::Nil))) => Ok(Lazy::Defer(node, x, cont)),
                     (x, y) => {
@@ -1,5 +1,8 @@
 pub mod dot;
+pub mod num;
 
+use std::borrow::Cow;
+use std::cmp;
 use commands::ast::*;
 
 // workaround for quoting operators
@@ -10,17 +13,57 @@ macro_rules! op {
     }
 }
 
+// integer arithmetic wraps on overflow and nulls propagate (k semantics)
+macro_rules! int_op {
+    ($l:tt, $r:tt, $f:ident, $null:expr) => {
+        if $l == $null || $r == $null { $null } else { $l.$f($r) }
+    };
+}
+
 macro_rules! dyad_map_expr {
+    ($l:tt, +, $r:tt,Number) => {
+        int_op!($l, $r, wrapping_add, NULL_INT)
+    };
+    ($l:tt, -, $r:tt,Number) => {
+        int_op!($l, $r, wrapping_sub, NULL_INT)
+    };
+    ($l:tt, *, $r:tt,Number) => {
+        int_op!($l, $r, wrapping_mul, NULL_INT)
+    };
+    ($l:tt, +, $r:tt,Int32) => {
+        int_op!($l, $r, wrapping_add, i32::MIN)
+    };
+    ($l:tt, -, $r:tt,Int32) => {
+        int_op!($l, $r, wrapping_sub, i32::MIN)
+    };
+    ($l:tt, *, $r:tt,Int32) => {
+        int_op!($l, $r, wrapping_mul, i32::MIN)
+    };
+    ($l:tt, +, $r:tt,Short) => {
+        int_op!($l, $r, wrapping_add, i16::MIN)
+    };
+    ($l:tt, -, $r:tt,Short) => {
+        int_op!($l, $r, wrapping_sub, i16::MIN)
+    };
+    ($l:tt, *, $r:tt,Short) => {
+        int_op!($l, $r, wrapping_mul, i16::MIN)
+    };
     ($l:tt, =, $r:tt,Float) => {
         if ($l - $r).abs() < 1e-10 { 1 } else { 0 } // TODO: Float tolerance
     };
-    ($l:tt, =, $r:tt,Number) => {
+    ($l:tt, =, $r:tt,Real) => {
+        if ($l - $r).abs() < 1e-6 { 1 } else { 0 } // TODO: Float tolerance
+    };
+    ($l:tt, =, $r:tt, $sink:tt) => {
         if $l == $r { 1 } else { 0 }
     };
     ($l:tt, !=, $r:tt,Float) => {
         if ($l - $r).abs() > 1e-10 { 1 } else { 0 } // TODO: Float tolerance
     };
-    ($l:tt, !=, $r:tt,Number) => {
+    ($l:tt, !=, $r:tt,Real) => {
+        if ($l - $r).abs() > 1e-6 { 1 } else { 0 } // TODO: Float tolerance
+    };
+    ($l:tt, !=, $r:tt, $sink:tt) => {
         if $l != $r { 1 } else { 0 }
     };
     ($l:tt, >, $r:tt, $sink:tt) => {
@@ -45,34 +88,36 @@ macro_rules! dyad_arith_match{
     ( $name: expr, $l:expr, $r:expr, $( [ $op:tt, $atype:tt, $atom:ident, $vec:ident, $r_atom:ident, $r_vec:ident ] ),* ) => {
         match ($l, $r) {
             $(
-                (&AST::Atom(Atom::Value(Value::$atom(l))),
-                 &AST::Atom(Atom::Value(Value::$atom(r)))) =>
-                    Ok( AST::Atom(Atom::Value(Value::$r_atom(dyad_map_expr!(l,$op,r,$atom)))) ),
+                (&Value::$atom(l), &Value::$atom(r)) =>
+                    Ok(Value::$r_atom(dyad_map_expr!(l,$op,r,$atom))),
 
-                (&AST::Atom(Atom::Value(Value::$atom(ref l))),
-                 &AST::Atom(Atom::Value(Value::$vec(ref r)))) => {
+                (&Value::$atom(l), &Value::$vec(ref r)) => {
                     let a: Vec<$atype> = r.iter()
-                        .map(|x| dyad_map_expr!(l,$op,x,$atom))
+                        .map(|&x| dyad_map_expr!(l,$op,x,$atom))
                         .collect::<Vec<$atype>>();
-                    Ok( AST::Atom(Atom::Value(Value::$r_vec(a))) )
+                    Ok(Value::$r_vec(a))
                 },
 
-                (&AST::Atom(Atom::Value(Value::$vec(ref l))),
-                 &AST::Atom(Atom::Value(Value::$atom(ref r)))) => {
+                (&Value::$vec(ref l), &Value::$atom(r)) => {
                     let a: Vec<$atype> = l.iter()
-                        .map(|x| dyad_map_expr!(x,$op,r,$atom))
+                        .map(|&x| dyad_map_expr!(x,$op,r,$atom))
                         .collect::<Vec<$atype>>();
-                    Ok( AST::Atom(Atom::Value(Value::$r_vec(a))) )
+                    Ok(Value::$r_vec(a))
                 },
 
-                (&AST::Atom(Atom::Value(Value::$vec(ref l))),
-                 &AST::Atom(Atom::Value(Value::$vec(ref r)))) => {
+                (&Value::$vec(ref l), &Value::$vec(ref r)) if l.len() == r.len() => {
                     let a: Vec<$atype> = l.iter()
                         .zip(r)
-                        .map(|(l,r)| dyad_map_expr!(l,$op,r,$atom))
+                        .map(|(&l,&r)| dyad_map_expr!(l,$op,r,$atom))
                         .collect::<Vec<$atype>>();
-                    Ok( AST::Atom(Atom::Value(Value::$r_vec(a))) )
-                },                
+                    Ok(Value::$r_vec(a))
+                },
+
+                (&Value::$vec(ref l), &Value::$vec(ref r)) =>
+                    Err(Error::EvalError {
+                        desc: format!("{} length error", $name),
+                        ast: format!("{} {}", l.len(), r.len()),
+                    }),
             )*
             _ =>
                 Err(Error::EvalError {
@@ -86,26 +131,33 @@ macro_rules! dyad_arith_match{
 macro_rules! dyad_arith{
     ($module:ident, $name:tt, $op:tt, $atype:tt, $r_atom:ident, $r_vec:ident) => {
         mod $module {
-            use commands::ast::Value;
-            use commands::ast::{AST, Atom, Error};
+            use commands::ast::{Value, Error, NULL_INT};
 
-            pub fn eval<'a>(l: &'a AST<'a>, r: &'a AST<'a>) -> Result<AST<'a>, Error> {
+            pub fn eval(l: &Value, r: &Value) -> Result<Value, Error> {
 
                 dyad_arith_match!( $name, l, r,
+                                   [$op, $atype, Bool, VecBool, $r_atom, $r_vec],
+                                   [$op, $atype, Byte, VecByte, $r_atom, $r_vec],
+                                   [$op, $atype, Short, VecShort, $r_atom, $r_vec],
+                                   [$op, $atype, Int32, VecInt32, $r_atom, $r_vec],
                                    [$op, $atype, Number, VecInt, $r_atom, $r_vec],
+                                   [$op, $atype, Real, VecReal, $r_atom, $r_vec],
                                    [$op, $atype, Float, VecFloat, $r_atom, $r_vec] )
             }
         }
     };
     ($module:ident, $name:tt, $op:tt) => {
         mod $module {
-            use commands::ast::Value;
-            use commands::ast::{AST, Atom, Error};
+            use std::{i16, i32};
+            use commands::ast::{Value, Error, NULL_INT};
 
-            pub fn eval<'a>(l: &'a AST<'a>, r: &'a AST<'a>) -> Result<AST<'a>, Error> {
+            pub fn eval(l: &Value, r: &Value) -> Result<Value, Error> {
 
                 dyad_arith_match!( $name, l, r,
+                                   [$op, i16, Short, VecShort, Short, VecShort],
+                                   [$op, i32, Int32, VecInt32, Int32, VecInt32],
                                    [$op, i64, Number, VecInt, Number, VecInt],
+                                   [$op, f32, Real, VecReal, Real, VecReal],
                                    [$op, f64, Float, VecFloat, Float, VecFloat] )
             }
         }
@@ -123,18 +175,83 @@ dyad_arith!(ge, "Dyad ge", >=, i64, Number, VecInt);
 dyad_arith!(lt, "Dyad lt", <, i64, Number, VecInt);
 dyad_arith!(le, "Dyad le", <=, i64, Number, VecInt);
 
-pub fn eval<'ast>(verb: Verb, left: &'ast AST<'ast>, right: &'ast AST<'ast>) -> Result<AST<'ast>, Error> {
+// Numeric promotion ladder (see num::RANK_*). Operands of different
+// numeric types are widened to a common rank before the verb is applied:
+// bool and byte arithmetic yields int, real mixed with long yields float,
+// and % always yields real or float.
+
+fn target(verb: Verb, l: &Value, r: &Value) -> u8 {
+    let (lr, rr) = (num::rank(l), num::rank(r));
+    if lr == num::RANK_NONE || rr == num::RANK_NONE {
+        return num::RANK_NONE;
+    }
+    let mut to = cmp::max(lr, rr);
+    if to == num::RANK_REAL && cmp::min(lr, rr) == num::RANK_LONG {
+        to = num::RANK_FLOAT;
+    }
     match verb {
-        Verb::Plus => plus::eval(left, right),
-        Verb::Minus => minus::eval(left, right),
-        Verb::Times => mul::eval(left, right),
-        Verb::Divide => div::eval(left, right),
-        Verb::Eq => eq::eval(left, right),
-        Verb::NEq => neq::eval(left, right),
-        Verb::Gt => gt::eval(left, right),
-        Verb::Ge => ge::eval(left, right),
-        Verb::Lt => lt::eval(left, right),
-        Verb::Le => le::eval(left, right),
+        Verb::Plus | Verb::Minus | Verb::Times if to < num::RANK_SHORT => num::RANK_INT,
+        Verb::Divide if to < num::RANK_REAL => num::RANK_FLOAT,
+        _ => to,
+    }
+}
+
+pub fn widen<'v>(v: &'v Value, to: u8) -> Cow<'v, Value> {
+    if num::rank(v) == to {
+        return Cow::Borrowed(v);
+    }
+    match num::to_rank(v, to) {
+        Some(x) => Cow::Owned(x),
+        None => Cow::Borrowed(v),
+    }
+}
+
+pub fn promote<'v>(verb: Verb, l: &'v Value, r: &'v Value) -> (Cow<'v, Value>, Cow<'v, Value>) {
+    let to = target(verb, l, r);
+    (widen(l, to), widen(r, to))
+}
+
+// Atomic extension: a verb applied to a general list recurses into its
+// items, pairing them with the items of the other side when it is a vector
+// too, or with the whole other side when it is an atom.
+
+fn extend(verb: Verb, left: &Value, right: &Value) -> Result<Value, Error> {
+    let v = match (left.items(), right.items()) {
+        (Some(l), Some(r)) => {
+            if l.len() != r.len() {
+                return Err(Error::EvalError {
+                    desc: format!("{:?} length error", verb),
+                    ast: format!("{} {}", l.len(), r.len()),
+                });
+            }
+            try!(l.iter().zip(r.iter()).map(|(l, r)| eval_value(verb, l, r)).collect::<Result<Vec<Value>, Error>>())
+        }
+        (Some(l), None) => try!(l.iter().map(|l| eval_value(verb, l, right)).collect::<Result<Vec<Value>, Error>>()),
+        (None, Some(r)) => try!(r.iter().map(|r| eval_value(verb, left, r)).collect::<Result<Vec<Value>, Error>>()),
+        (None, None) => return Err(Error::InternalError),
+    };
+    Ok(Value::pack(v))
+}
+
+pub fn eval_value(verb: Verb, left: &Value, right: &Value) -> Result<Value, Error> {
+    match (left, right) {
+        (&Value::List(_), _) |
+        (_, &Value::List(_)) => return extend(verb, left, right),
+        _ => (),
+    }
+    let (l, r) = promote(verb, left, right);
+    let (l, r) = (&*l, &*r);
+    match verb {
+        Verb::Plus => plus::eval(l, r),
+        Verb::Minus => minus::eval(l, r),
+        Verb::Times => mul::eval(l, r),
+        Verb::Divide => div::eval(l, r),
+        Verb::Eq => eq::eval(l, r),
+        Verb::NEq => neq::eval(l, r),
+        Verb::Gt => gt::eval(l, r),
+        Verb::Ge => ge::eval(l, r),
+        Verb::Lt => lt::eval(l, r),
+        Verb::Le => le::eval(l, r),
         x => {
             Err(Error::EvalError {
                 desc: "Verb is not implemented".to_string(),
@@ -143,3 +260,57 @@ pub fn eval<'ast>(verb: Verb, left: &'ast AST<'ast>, right: &'ast AST<'ast>) ->
         }
     }
 }
+
+pub fn cast<'ast>(arena: &Arena<'ast>, left: &'ast AST<'ast>, right: &'ast AST<'ast>) -> Result<AST<'ast>, Error> {
+    match (left, right) {
+        (&AST::Atom(Atom::Value(Value::SymbolInt(s))), &AST::Atom(Atom::Value(ref v))) => {
+            let name = try!(arena.symbol_name(s).ok_or(Error::InternalError));
+            let to = try!(num::rank_of_name(&name));
+            match num::to_rank(v, to) {
+                Some(x) => Ok(AST::Atom(Atom::Value(x))),
+                None => {
+                    Err(Error::EvalError {
+                        desc: "Cast type error".to_string(),
+                        ast: format!("`{}${:?}", name, v),
+                    })
+                }
+            }
+        }
+        _ => {
+            Err(Error::EvalError {
+                desc: "Cast expects `type$value".to_string(),
+                ast: format!("{:?} {:?}", left, right),
+            })
+        }
+    }
+}
+
+// evaluated dicts are vectors of ASTs, take them as general lists
+pub fn value<'v, 'ast>(a: &'v AST<'ast>) -> Option<Cow<'v, Value>> {
+    match a {
+        &AST::Atom(Atom::Value(ref v)) => Some(Cow::Borrowed(v)),
+        &AST::Vector(ref x) => {
+            let mut v = Vec::with_capacity(x.len());
+            for i in x {
+                match i {
+                    &AST::Atom(Atom::Value(ref a)) => v.push(a.clone()),
+                    _ => return None,
+                }
+            }
+            Some(Cow::Owned(Value::List(v)))
+        }
+        _ => None,
+    }
+}
+
+pub fn eval<'ast>(verb: Verb, left: &'ast AST<'ast>, right: &'ast AST<'ast>) -> Result<AST<'ast>, Error> {
+    match (value(left), value(right)) {
+        (Some(l), Some(r)) => eval_value(verb, &l, &r).map(|v| AST::Atom(Atom::Value(v))),
+        _ => {
+            Err(Error::EvalError {
+                desc: "Verb is not implemented".to_string(),
+                ast: format!("{:?} {:?} {:?}", verb, left, right),
+            })
+        }
+    }
+}
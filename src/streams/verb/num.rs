@@ -0,0 +1,166 @@
+// Numeric widths and conversions between them.
+// Integral values travel through i64, fractional through f64,
+// so that nulls (0N, 0n) survive widening and narrowing. Floats round to integers, as in q.
+
+use std::{i16, i32};
+use commands::ast::{Value, Error, NULL_INT, NULL_FLOAT};
+
+pub const RANK_NONE: u8 = 0;
+pub const RANK_BOOL: u8 = 1;
+pub const RANK_BYTE: u8 = 2;
+pub const RANK_SHORT: u8 = 3;
+pub const RANK_INT: u8 = 4;
+pub const RANK_LONG: u8 = 5;
+pub const RANK_REAL: u8 = 6;
+pub const RANK_FLOAT: u8 = 7;
+
+pub trait Num: Copy {
+    fn is_float() -> bool;
+    fn from_i64(x: i64) -> Self;
+    fn from_f64(x: f64) -> Self;
+    fn to_i64(self) -> i64;
+    fn to_f64(self) -> f64;
+}
+
+impl Num for bool {
+    fn is_float() -> bool { false }
+    fn from_i64(x: i64) -> Self { x != 0 && x != NULL_INT }
+    fn from_f64(x: f64) -> Self { x != 0.0 && !x.is_nan() }
+    fn to_i64(self) -> i64 { self as i64 }
+    fn to_f64(self) -> f64 { self as u8 as f64 }
+}
+
+impl Num for u8 {
+    fn is_float() -> bool { false }
+    fn from_i64(x: i64) -> Self { x as u8 }
+    fn from_f64(x: f64) -> Self { x.round() as u8 }
+    fn to_i64(self) -> i64 { self as i64 }
+    fn to_f64(self) -> f64 { self as f64 }
+}
+
+impl Num for i16 {
+    fn is_float() -> bool { false }
+    fn from_i64(x: i64) -> Self { if x == NULL_INT { i16::MIN } else { x as i16 } }
+    fn from_f64(x: f64) -> Self { if x.is_nan() { i16::MIN } else { x.round() as i16 } }
+    fn to_i64(self) -> i64 { if self == i16::MIN { NULL_INT } else { self as i64 } }
+    fn to_f64(self) -> f64 { if self == i16::MIN { NULL_FLOAT } else { self as f64 } }
+}
+
+impl Num for i32 {
+    fn is_float() -> bool { false }
+    fn from_i64(x: i64) -> Self { if x == NULL_INT { i32::MIN } else { x as i32 } }
+    fn from_f64(x: f64) -> Self { if x.is_nan() { i32::MIN } else { x.round() as i32 } }
+    fn to_i64(self) -> i64 { if self == i32::MIN { NULL_INT } else { self as i64 } }
+    fn to_f64(self) -> f64 { if self == i32::MIN { NULL_FLOAT } else { self as f64 } }
+}
+
+impl Num for i64 {
+    fn is_float() -> bool { false }
+    fn from_i64(x: i64) -> Self { x }
+    fn from_f64(x: f64) -> Self { if x.is_nan() { NULL_INT } else { x.round() as i64 } }
+    fn to_i64(self) -> i64 { self }
+    fn to_f64(self) -> f64 { if self == NULL_INT { NULL_FLOAT } else { self as f64 } }
+}
+
+impl Num for f32 {
+    fn is_float() -> bool { true }
+    fn from_i64(x: i64) -> Self { if x == NULL_INT { NULL_FLOAT as f32 } else { x as f32 } }
+    fn from_f64(x: f64) -> Self { x as f32 }
+    fn to_i64(self) -> i64 { if self.is_nan() { NULL_INT } else { self as i64 } }
+    fn to_f64(self) -> f64 { self as f64 }
+}
+
+impl Num for f64 {
+    fn is_float() -> bool { true }
+    fn from_i64(x: i64) -> Self { if x == NULL_INT { NULL_FLOAT } else { x as f64 } }
+    fn from_f64(x: f64) -> Self { x }
+    fn to_i64(self) -> i64 { if self.is_nan() { NULL_INT } else { self as i64 } }
+    fn to_f64(self) -> f64 { self }
+}
+
+#[inline]
+pub fn conv<S: Num, D: Num>(s: S) -> D {
+    if S::is_float() {
+        D::from_f64(s.to_f64())
+    } else {
+        D::from_i64(s.to_i64())
+    }
+}
+
+pub fn rank(v: &Value) -> u8 {
+    match *v {
+        Value::Bool(_) |
+        Value::VecBool(_) => RANK_BOOL,
+        Value::Byte(_) |
+        Value::VecByte(_) => RANK_BYTE,
+        Value::Short(_) |
+        Value::VecShort(_) => RANK_SHORT,
+        Value::Int32(_) |
+        Value::VecInt32(_) => RANK_INT,
+        Value::Number(_) |
+        Value::VecInt(_) => RANK_LONG,
+        Value::Real(_) |
+        Value::VecReal(_) => RANK_REAL,
+        Value::Float(_) |
+        Value::VecFloat(_) => RANK_FLOAT,
+        _ => RANK_NONE,
+    }
+}
+
+macro_rules! convert {
+    ($v:expr, $t:ty, $atom:ident, $vec:ident) => {
+        match *$v {
+            Value::Bool(x) => Some(Value::$atom(conv::<bool, $t>(x))),
+            Value::Byte(x) => Some(Value::$atom(conv::<u8, $t>(x))),
+            Value::Short(x) => Some(Value::$atom(conv::<i16, $t>(x))),
+            Value::Int32(x) => Some(Value::$atom(conv::<i32, $t>(x))),
+            Value::Number(x) => Some(Value::$atom(conv::<i64, $t>(x))),
+            Value::Real(x) => Some(Value::$atom(conv::<f32, $t>(x))),
+            Value::Float(x) => Some(Value::$atom(conv::<f64, $t>(x))),
+            Value::VecBool(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<bool, $t>(e)).collect())),
+            Value::VecByte(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<u8, $t>(e)).collect())),
+            Value::VecShort(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<i16, $t>(e)).collect())),
+            Value::VecInt32(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<i32, $t>(e)).collect())),
+            Value::VecInt(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<i64, $t>(e)).collect())),
+            Value::VecReal(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<f32, $t>(e)).collect())),
+            Value::VecFloat(ref x) => Some(Value::$vec(x.iter().map(|&e| conv::<f64, $t>(e)).collect())),
+            _ => None,
+        }
+    }
+}
+
+// converts any numeric atom or vector (general lists item-wise) to the given rank
+pub fn to_rank(v: &Value, to: u8) -> Option<Value> {
+    if let Value::List(ref x) = *v {
+        return x.iter().map(|e| to_rank(e, to)).collect::<Option<Vec<Value>>>().map(Value::pack);
+    }
+    match to {
+        RANK_BOOL => convert!(v, bool, Bool, VecBool),
+        RANK_BYTE => convert!(v, u8, Byte, VecByte),
+        RANK_SHORT => convert!(v, i16, Short, VecShort),
+        RANK_INT => convert!(v, i32, Int32, VecInt32),
+        RANK_LONG => convert!(v, i64, Number, VecInt),
+        RANK_REAL => convert!(v, f32, Real, VecReal),
+        RANK_FLOAT => convert!(v, f64, Float, VecFloat),
+        _ => None,
+    }
+}
+
+// `bool$x, `j$x, ... both long names and q type chars are accepted
+pub fn rank_of_name(name: &str) -> Result<u8, Error> {
+    match name {
+        "b" | "bool" | "boolean" => Ok(RANK_BOOL),
+        "x" | "byte" => Ok(RANK_BYTE),
+        "h" | "short" => Ok(RANK_SHORT),
+        "i" | "int" => Ok(RANK_INT),
+        "j" | "long" => Ok(RANK_LONG),
+        "e" | "real" => Ok(RANK_REAL),
+        "f" | "float" => Ok(RANK_FLOAT),
+        x => {
+            Err(Error::EvalError {
+                desc: "Unknown cast type".to_string(),
+                ast: format!("`{}", x),
+            })
+        }
+    }
+}
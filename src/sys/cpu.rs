@@ -0,0 +1,100 @@
+// The machine as /sys/devices/system/cpu shows it: which package, core and NUMA node
+// each online cpu belongs to. Empty where sysfs is missing, nothing gets pinned then.
+
+use std::fs::{self, File};
+use std::io::Read;
+
+const CPU: &'static str = "/sys/devices/system/cpu";
+const NODE: &'static str = "/sys/devices/system/node";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cpu {
+    pub id: usize,
+    pub node: usize,
+    pub package: usize,
+    pub core: usize, // hyperthreads of one core share it
+}
+
+fn read(path: &str) -> Option<String> {
+    let mut s = String::new();
+    match File::open(path).and_then(|mut f| f.read_to_string(&mut s)) {
+        Ok(_) => Some(s.trim().to_string()),
+        Err(_) => None,
+    }
+}
+
+fn number(path: &str) -> Option<usize> {
+    read(path).and_then(|s| s.parse().ok())
+}
+
+pub fn parse_list(s: &str) -> Vec<usize> {
+    // the kernel's cpu lists, as 0-3,8,10-11
+    let mut v = vec![];
+    for r in s.trim().split(',').filter(|r| !r.is_empty()) {
+        let mut ends = r.splitn(2, '-').map(|x| x.trim().parse::<usize>());
+        match (ends.next(), ends.next()) {
+            (Some(Ok(a)), None) => v.push(a),
+            (Some(Ok(a)), Some(Ok(b))) if a <= b => v.extend(a..b + 1),
+            _ => return vec![],
+        }
+    }
+    v
+}
+
+fn nodes() -> Vec<(usize, Vec<usize>)> {
+    // (node, its cpus)
+    let mut v = vec![];
+    if let Ok(dir) = fs::read_dir(NODE) {
+        for e in dir.filter_map(|e| e.ok()) {
+            let name = e.file_name().to_string_lossy().into_owned();
+            if !name.starts_with("node") {
+                continue;
+            }
+            if let (Ok(n), Some(l)) = (name[4..].parse(), read(&format!("{}/{}/cpulist", NODE, name))) {
+                v.push((n, parse_list(&l)));
+            }
+        }
+    }
+    v
+}
+
+pub fn cpus() -> Vec<Cpu> {
+    let online = read(&format!("{}/online", CPU)).map(|s| parse_list(&s)).unwrap_or(vec![]);
+    let nodes = nodes();
+    online.into_iter()
+        .map(|id| {
+            let topo = format!("{}/cpu{}/topology", CPU, id);
+            Cpu {
+                id: id,
+                node: nodes.iter().find(|n| n.1.contains(&id)).map_or(0, |n| n.0),
+                package: number(&format!("{}/physical_package_id", topo)).unwrap_or(0),
+                core: number(&format!("{}/core_id", topo)).unwrap_or(id),
+            }
+        })
+        .collect()
+}
+
+pub fn spread(cpus: &[Cpu], n: usize) -> Vec<usize> {
+    // one cpu per physical core before any hyperthread sibling, node by node,
+    // so neighbouring cores share a node. More cores than cpus wrap around.
+    let mut order: Vec<&Cpu> = cpus.iter().collect();
+    order.sort_by_key(|c| (c.node, c.package, c.core, c.id));
+    let mut first = vec![];
+    let mut rest = vec![];
+    for c in order {
+        if first.iter().any(|f: &&Cpu| (f.package, f.core) == (c.package, c.core)) {
+            rest.push(c);
+        } else {
+            first.push(c);
+        }
+    }
+    first.extend(rest);
+    if first.is_empty() {
+        return vec![];
+    }
+    (0..n).map(|i| first[i % first.len()].id).collect()
+}
+
+pub fn node_of(cpus: &[Cpu], cpu: usize) -> usize {
+    cpus.iter().find(|c| c.id == cpu).map_or(0, |c| c.node)
+}
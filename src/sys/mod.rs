@@ -1,2 +1,4 @@
 pub mod unix;
-pub use self::unix::set_affinity;
+pub mod cpu;
+pub use self::unix::{set_affinity, bind_memory, catch_signals, caught, block_signals, unblock_signals, signal_fd,
+                     read_signals, SIGNALS};
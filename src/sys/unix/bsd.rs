@@ -1,2 +1,24 @@
 //
-pub fn set_affinity(cpu: usize) {}
\ No newline at end of file
+use std::os::unix::io::RawFd;
+
+pub fn set_affinity(cpu: usize) {}
+
+pub fn bind_memory(ptr: *const u8, len: usize, node: usize) -> bool {
+    false
+}
+
+pub fn block_signals() -> bool {
+    false
+}
+
+pub fn unblock_signals() -> bool {
+    false
+}
+
+pub fn signal_fd() -> Option<RawFd> {
+    None
+}
+
+pub fn read_signals(fd: RawFd) -> Vec<usize> {
+    vec![]
+}
@@ -1,9 +1,98 @@
 use libc;
 use nix::sched::{self, CpuSet};
+use std::os::unix::io::RawFd;
+use std::{mem, ptr};
+use super::SIGNALS;
 
 pub fn set_affinity(cpu_id: usize) {
     let id = unsafe { libc::pthread_self() as isize };
     let mut cpu = CpuSet::new();
     cpu.set(cpu_id);
     sched::sched_setaffinity(id, &cpu);
-}
\ No newline at end of file
+}
+
+#[cfg(target_arch = "x86_64")]
+const SYS_MBIND: libc::c_long = 237;
+#[cfg(target_arch = "aarch64")]
+const SYS_MBIND: libc::c_long = 235;
+
+const MPOL_BIND: libc::c_long = 2;
+const MPOL_MF_MOVE: libc::c_long = 2;
+const PAGE: usize = 4096;
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub fn bind_memory(ptr: *const u8, len: usize, node: usize) -> bool {
+    // moves the pages of [ptr, ptr + len) to the node, false if the kernel says no
+    if node >= 64 || len == 0 {
+        return false;
+    }
+    let start = ptr as usize & !(PAGE - 1);
+    let end = (ptr as usize + len + PAGE - 1) & !(PAGE - 1);
+    let mask: libc::c_ulong = 1 << node;
+    let r = unsafe {
+        libc::syscall(SYS_MBIND,
+                      start as libc::c_long,
+                      (end - start) as libc::c_long,
+                      MPOL_BIND,
+                      &mask as *const libc::c_ulong,
+                      64 as libc::c_long,
+                      MPOL_MF_MOVE)
+    };
+    r == 0
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn bind_memory(ptr: *const u8, len: usize, node: usize) -> bool {
+    false
+}
+
+fn signal_set() -> libc::sigset_t {
+    let mut set: libc::sigset_t = unsafe { mem::zeroed() };
+    unsafe {
+        libc::sigemptyset(&mut set);
+        for &s in &SIGNALS {
+            libc::sigaddset(&mut set, s);
+        }
+    }
+    set
+}
+
+pub fn block_signals() -> bool {
+    // threads spawned afterwards inherit the mask, so this goes before the cores start
+    let set = signal_set();
+    unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut()) == 0 }
+}
+
+pub fn unblock_signals() -> bool {
+    // for the thread that catches them with a handler instead
+    let set = signal_set();
+    unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, ptr::null_mut()) == 0 }
+}
+
+pub fn signal_fd() -> Option<RawFd> {
+    // None leaves the signals to the handler
+    if !block_signals() {
+        return None;
+    }
+    let set = signal_set();
+    match unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) } {
+        -1 => None,
+        fd => Some(fd),
+    }
+}
+
+pub fn read_signals(fd: RawFd) -> Vec<usize> {
+    // everything pending, the fd is edge triggered
+    let mut v = vec![];
+    let mut info: [libc::signalfd_siginfo; 8] = unsafe { mem::zeroed() };
+    let size = mem::size_of::<libc::signalfd_siginfo>();
+    loop {
+        let n = unsafe { libc::read(fd, info.as_mut_ptr() as *mut libc::c_void, size * info.len()) };
+        if n <= 0 {
+            return v;
+        }
+        for i in &info[..n as usize / size] {
+            v.push(i.ssi_signo as usize);
+        }
+    }
+}
@@ -2,10 +2,42 @@
 mod linux;
 
 #[cfg(any(target_os = "linux"))]
-pub use ::sys::unix::linux::set_affinity;
+pub use ::sys::unix::linux::{set_affinity, bind_memory, block_signals, unblock_signals, signal_fd, read_signals};
 
 #[cfg(any(target_os = "macos"))]
 mod bsd;
 
 #[cfg(any(target_os = "macos"))]
-pub use ::sys::unix::bsd::set_affinity;
\ No newline at end of file
+pub use ::sys::unix::bsd::{set_affinity, bind_memory, block_signals, unblock_signals, signal_fd, read_signals};
+
+// The signals the BSP reacts to. Where there is a signalfd they are blocked in every
+// thread and read from it, elsewhere a handler only notes them for the BSP to look at.
+
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use libc;
+
+pub const SIGNALS: [libc::c_int; 3] = [libc::SIGHUP, libc::SIGINT, libc::SIGTERM];
+
+static CAUGHT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+extern "C" fn note(sig: libc::c_int) {
+    CAUGHT.store(sig as usize, Ordering::SeqCst);
+}
+
+pub fn catch_signals() {
+    // the cores were started with the signals blocked, this thread takes them back
+    unblock_signals();
+    for &s in &SIGNALS {
+        unsafe {
+            libc::signal(s, note as libc::sighandler_t);
+        }
+    }
+}
+
+pub fn caught() -> Option<usize> {
+    // the last signal since the previous look
+    match CAUGHT.swap(0, Ordering::SeqCst) {
+        0 => None,
+        s => Some(s),
+    }
+}
@@ -4,14 +4,26 @@ extern crate kernel;
 
 use kernel::commands::ast::*;
 use kernel::streams::interpreter::*;
-use kernel::reactors::task::{Termination, Context, Poll, Task};
+use kernel::reactors::task::{Termination, Context, Poll, Task, State};
 use kernel::reactors::job::Job;
+use kernel::reactors::runq::NORMAL;
 use kernel::reactors::cps::CpsTask;
-use kernel::reactors::scheduler::Scheduler;
+use kernel::reactors::sup::Strategy;
+use kernel::reactors::stats;
+use kernel::reactors::scheduler::{Scheduler, TASKS_MAX_CNT};
 use kernel::handle::{self, into_raw, UnsafeShared, use_, from_raw};
-use kernel::intercore::bus::Memory;
-use kernel::intercore::message::Message;
-use kernel::intercore::server::intercore;
+use kernel::intercore::bus::{self, Memory, Channel, Overflow};
+use kernel::intercore::message::{Message, AckCall, AckPub, AckSub, Spawn, Names, QoS, Kill, Tasks, Watch, Watcher,
+                                 Down, Metrics, Halt};
+use kernel::intercore::wire::{self, Reader};
+use kernel::commands::source;
+use kernel::commands::command;
+use kernel::intercore::server::{intercore, delivery, Registered};
+use kernel::intercore::topology::{Topology, Shape};
+use kernel::sys::cpu::{self, Cpu};
+use kernel::intercore::pending::Pending;
+use std::time::{Duration, Instant};
+use std::thread;
 use kernel::queues::publisher::{Publisher, Subscriber};
 
 fn av<'a>(x: Value) -> AST<'a> {
@@ -193,6 +205,19 @@ pub fn k_adverb() {
 }
 
 
+#[test]
+pub fn k_dotted_names() {
+    // only .z. names take dots, a.b is still a dyad
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&".z.ts".to_string());
+    assert!(format!("{:?}", code).starts_with("Vector([Atom(NameInt("));
+    let code = h.borrow_mut().parse(&"a.b".to_string());
+    assert!(format!("{:?}", code).starts_with("Vector([Atom(Verb("));
+}
+
+
 #[test]
 pub fn k_reduce() {
     let mut mem = Memory::new();
@@ -201,8 +226,8 @@ pub fn k_reduce() {
     let code = h.borrow_mut().parse(&"+/{x*y}[(1;3;4;5;6);(2;6;2;1;3)]".to_string());
     assert_eq!(format!("{:?}", code),
                "Vector([Atom(Adverb(Over, Atom(Verb(Plus, Atom(Value(Nil)), Atom(Value(Nil)))), \
-                Atom(Call(Atom(Lambda(None, Atom(NameInt(0)), Vector([Atom(Verb(Times, Atom(NameInt(0)), \
-                Atom(NameInt(1))))]))), Atom(Dict(Vector([Atom(List(Atom(Value(VecInt([1, 3, 4, 5, 6]))))), \
+                Atom(Call(Atom(Lambda(None, Vector([Atom(NameInt(0)), Atom(NameInt(1))]), \
+                Vector([Atom(Verb(Times, Atom(NameInt(0)), Atom(NameInt(1))))]))), Atom(Dict(Vector([Atom(List(Atom(Value(VecInt([1, 3, 4, 5, 6]))))), \
                 Atom(List(Atom(Value(VecInt([2, 6, 2, 1, 3])))))])))))))])");
 }
 
@@ -243,7 +268,7 @@ pub fn k_nested_dict() {
 
     let code = h.borrow_mut().parse(&"a:10;[1;2;[a+a;[4+a;3];2];5]".to_string());
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
-               "#a[1;2;#a[20;#a[14;3];2];5]");
+               "#a[1;2;#a[20;#i[14;3];2];5]");
 }
 
 #[test]
@@ -263,7 +288,7 @@ pub fn k_nested_list() {
 
     let code = h.borrow_mut().parse(&"a:10;(1;2;(a+a;(4+a;3);2);5)".to_string());
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
-               "#a[1;2;#a[20;#a[14;3];2];5]");
+               "#a[1;2;#a[20;#i[14;3];2];5]");
 }
 
 #[test]
@@ -398,7 +423,7 @@ pub fn k_tensor0() {
 
     let code = h.borrow_mut().parse(&"g:1;b:1;[[g;g*b;1;0];[g*b;g;180;0];[0;0;270;0];[0;0;0;1]]".to_string());
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
-               "#a[#a[1;1;1;0];#a[1;1;180;0];#i[0;0;270;0];#i[0;0;270;0];#i[0;0;0;1];#i[0;0;0;1]]");
+               "#a[#a[1;1;1;0];#a[1;1;180;0];#i[0;0;270;0];#i[0;0;0;1]]");
 }
 
 #[test]
@@ -408,7 +433,7 @@ pub fn k_tensor1() {
 
     let code = h.borrow_mut().parse(&"a:10;[[[a;2;3];[1;[a;4];3]];[1;2]]".to_string());
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
-               "#a[#a[#a[10;2;3];#a[1;#a[10;4];3]];#i[1;2];#i[1;2]]");
+               "#a[#a[#a[10;2;3];#a[1;#a[10;4];3]];#i[1;2]]");
 }
 
 #[test]
@@ -418,7 +443,7 @@ pub fn k_tensor2() {
 
     let code = h.borrow_mut().parse(&"a:10;[[[a;2;3];[[a;4];[3;0]]];[1;2]]".to_string());
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
-               "#a[#a[#a[10;2;3];#a[#a[10;4];#i[3;0];#i[3;0]]];#i[1;2];#i[1;2]]");
+               "#a[#a[#a[10;2;3];#a[#a[10;4];#i[3;0]]];#i[1;2]]");
 }
 
 #[test]
@@ -428,7 +453,7 @@ pub fn k_tensor3() {
 
     let code = h.borrow_mut().parse(&"a:10;[[[[a;2;3];[[a;4];[3;0]]];[1;2]];1]".to_string());
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
-               "#a[#a[#a[#a[10;2;3];#a[#a[10;4];#i[3;0];#i[3;0]]];#i[1;2];#i[1;2]];1]");
+               "#a[#a[#a[#a[10;2;3];#a[#a[10;4];#i[3;0]]];#i[1;2]];1]");
 }
 
 #[test]
@@ -526,9 +551,9 @@ pub fn k_pubsub() {
     let code = "p0:pub[0;8]; s1:sub[0;p0]; s2:sub[0;p0]; snd[p0;11]; snd[p0;12]; print[rcv s1; rcv s2; rcv s1; rcv s2]";
     let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
                                   Termination::Corecursive,
-                                  Some(code));
+                                  Some(code)).unwrap();
 
-    let t = into_raw(sched.tasks.get_mut(shell.0).expect("no shell"));
+    let t = into_raw(sched.task(shell.0).expect("no shell"));
     from_raw(t).0.exec(Some(code));
     let mut poll;
     let mut msg1 = Message::Nop;
@@ -564,6 +589,1165 @@ pub fn k_pubsub() {
     }
 }
 
+fn pair(loopback: bool) -> Vec<Channel> {
+    // cores 0 and 1 hear each other first, then themselves with loopback
+    let mut c0 = Channel::new(0, 16);
+    let mut c1 = Channel::new(1, 16);
+    c0.subscribers.push(c1.publisher.subscribe());
+    c1.subscribers.push(c0.publisher.subscribe());
+    if loopback {
+        c0.subscribers.push(c0.publisher.subscribe());
+        c1.subscribers.push(c1.publisher.subscribe());
+    }
+    vec![c0, c1]
+}
+
+fn cores<'a>(cs: Vec<Channel>) -> Vec<Scheduler<'a>> {
+    // one scheduler per channel, the tests drive them by hand
+    cs.into_iter().map(Scheduler::with_channel2).collect()
+}
+
+#[test]
+pub fn k_spawn() {
+    let mut s = cores(pair(false));
+    let a = into_raw(&mut s[0]);
+    let b = into_raw(&mut s[1]);
+    let code = "a:10;h:spawn[1;{a+2}];wait h";
+    let shell = from_raw(a).spawn(Job::Cps(CpsTask::new(from_raw(a).mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+
+    let t = into_raw(from_raw(a).task(shell.0).expect("no shell"));
+    match from_raw(t).0.poll(Context::Nil, from_raw(a)) {
+        Poll::Yield(Context::Intercore(&Message::Spawn(ref m))) => assert_eq!(m.txt, "a:10;{[x]a+2}[]"),
+        _ => assert_eq!(1, 0),
+    }
+    from_raw(b).poll_bus(); // spawn on core 1, AckSpawn back
+    from_raw(a).poll_bus(); // shell resumes with the handle and waits
+    while !from_raw(b).results.contains_key(&0) {
+        from_raw(b).poll_tasks();
+    }
+    from_raw(b).poll_bus(); // Join is answered with the result
+
+    let mut msg = from_raw(a).bus.subscribers[0].recv().expect("no ack").clone();
+    from_raw(a).bus.subscribers[0].commit();
+    let ctx = intercore(from_raw(a), Some(use_(&mut msg)), &mut from_raw(a).bus);
+    match delivery(ctx, from_raw(a)) {
+        Poll::End(Context::Node(n)) => assert_eq!(format!("{}", n), "12"),
+        _ => assert_eq!(1, 0),
+    }
+}
+
+#[test]
+pub fn k_spawn_args() {
+    // malformed spawn and wait arguments fail the evaluation instead of the core
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+    h.borrow_mut().define_primitives();
+    for code in &["spawn[1]", "spawn[`a;{1}]", "spawn[1;{1};300]", "wait 5"] {
+        let code = h.borrow_mut().parse(&code.to_string());
+        assert!(h.borrow_mut().run(code, Context::Nil, None).is_err());
+    }
+}
+
+#[test]
+pub fn k_call() {
+    let mut s = cores(pair(false));
+    let a = into_raw(&mut s[0]);
+    let b = into_raw(&mut s[1]);
+    let code = "k:6;1+call[1;{k*7}]";
+    let shell = from_raw(a).spawn(Job::Cps(CpsTask::new(from_raw(a).mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+
+    let t = into_raw(from_raw(a).task(shell.0).expect("no shell"));
+    match from_raw(t).0.poll(Context::Nil, from_raw(a)) {
+        Poll::Yield(Context::Intercore(&Message::Call(ref m))) => {
+            assert_eq!(m.corr, 1);
+            assert_eq!(m.txt, "k:6;{[x]k*7}[]")
+        }
+        _ => assert_eq!(1, 0),
+    }
+    from_raw(b).poll_bus(); // evaluated as a task on core 1
+    while !from_raw(b).results.contains_key(&0) {
+        from_raw(b).poll_tasks();
+    }
+
+    let mut msg = from_raw(a).bus.subscribers[0].recv().expect("no ack").clone();
+    from_raw(a).bus.subscribers[0].commit();
+    let ctx = intercore(from_raw(a), Some(use_(&mut msg)), &mut from_raw(a).bus);
+    match delivery(ctx, from_raw(a)) {
+        Poll::End(Context::Node(n)) => assert_eq!(format!("{}", n), "43"),
+        _ => assert_eq!(1, 0),
+    }
+}
+
+#[test]
+#[should_panic]
+pub fn k_topology_stats_port() {
+    Topology::from_script("cores:1;stats:70000");
+}
+
+#[test]
+pub fn k_topology() {
+    let t = Topology::from_script("cores:3;cap:16;shape:`ring;bsp:1");
+    assert_eq!(t,
+               Topology {
+                   cores: 3,
+                   cap: 16,
+                   shape: Shape::Ring,
+                   bsp: 1,
+                   ..Topology::default()
+               });
+    let ring = t.channels();
+    assert_eq!(ring[0].peers, vec![0, 2]);
+    assert_eq!(ring[2].peers, vec![1, 2]);
+    assert_eq!(ring[1].relay, vec![0, 2]);
+
+    let mesh = Topology::default().channels();
+    assert_eq!(mesh.len(), 4);
+    assert_eq!(mesh[1].peers, vec![0, 1, 2, 3]);
+    assert!(mesh[1].relay.is_empty());
+
+    let star = Topology { shape: Shape::Star, ..Topology::default() }.channels();
+    assert_eq!(star[2].peers, vec![0, 2]);
+    assert_eq!(star[0].relay, vec![1, 2, 3]);
+    assert!(star[2].relay.is_empty());
+}
+
+#[test]
+pub fn k_cpu_placement() {
+    assert_eq!(cpu::parse_list("0-2,5,7-8\n"), vec![0, 1, 2, 5, 7, 8]);
+    assert_eq!(cpu::parse_list("3-1"), vec![]);
+
+    // two nodes of two cores with two hyperthreads, siblings are numbered apart
+    let cpus: Vec<Cpu> = (0..8)
+        .map(|id| {
+            Cpu {
+                id: id,
+                node: id % 4 / 2,
+                package: id % 4 / 2,
+                core: id % 2,
+            }
+        })
+        .collect();
+    assert_eq!(cpu::spread(&cpus, 6), vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(cpu::spread(&cpus, 10)[8..].to_vec(), vec![0, 1]);
+    assert!(cpu::spread(&[], 4).is_empty());
+
+    let t = Topology::from_script("cores:3;pin:`auto");
+    assert!(t.auto);
+    assert_eq!(t.placement(&cpus), vec![Some((0, 0)), Some((1, 0)), Some((2, 1))]);
+    assert_eq!(t.placement(&[]), vec![None, None, None]);
+    let t = Topology { cores: 2, pin: vec![6, 3], ..Topology::default() };
+    assert_eq!(t.placement(&cpus), vec![Some((6, 1)), Some((3, 1))]);
+}
+
+#[test]
+pub fn k_bell() {
+    // a parked core wakes up as soon as a core it hears publishes
+    let mut cs = Topology { cores: 2, ..Topology::default() }.channels();
+    let ref mut s1 = Scheduler::with_channel2(cs.pop().unwrap());
+    let c0 = cs.pop().unwrap();
+    s1.bus.bell.unpark();
+    s1.bus.bell.park();
+    assert!(!s1.bus.heard());
+    let t = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        bus::send(&c0,
+                  Message::Names(Names {
+                      from: 0,
+                      to: 1,
+                      task_id: 0,
+                      corr: 1,
+                  }));
+        c0
+    });
+    let start = Instant::now();
+    s1.io.park(Duration::from_millis(5000));
+    s1.bus.bell.unpark();
+    assert!(start.elapsed() < Duration::from_millis(2000));
+    assert!(s1.bus.heard());
+    t.join().unwrap();
+}
+
+#[test]
+pub fn k_queue_bell() {
+    // a parked core wakes up when a data queue it reads from is committed to
+    let mut cs = Topology { cores: 2, ..Topology::default() }.channels();
+    let ref mut s1 = Scheduler::with_channel2(cs.pop().unwrap());
+    let bell = cs[0].bells[1].clone();
+    s1.bus.bell.unpark();
+    s1.bus.bell.park();
+    let t = thread::spawn(move || {
+        let mem = Memory::new();
+        let id = mem.add_publisher(Publisher::with_capacity(8), bus::TypeId::Int);
+        mem.wake(id, bell);
+        thread::sleep(Duration::from_millis(20));
+        mem.ring(id);
+    });
+    let start = Instant::now();
+    s1.io.park(Duration::from_millis(5000));
+    s1.bus.bell.unpark();
+    assert!(start.elapsed() < Duration::from_millis(2000));
+    t.join().unwrap();
+}
+
+#[test]
+pub fn k_ring_relay() {
+    // core 0 only reaches core 2 through core 1
+    let t = Topology { cores: 3, cap: 16, shape: Shape::Ring, ..Topology::default() };
+    let mut s = cores(t.channels());
+    let a = into_raw(&mut s[0]);
+    let b = into_raw(&mut s[1]);
+    let c = into_raw(&mut s[2]);
+    let code = "call[2;{6*7}]";
+    let shell = from_raw(a).spawn(Job::Cps(CpsTask::new(from_raw(a).mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+    let t = into_raw(from_raw(a).task(shell.0).expect("no shell"));
+    match from_raw(t).0.poll(Context::Nil, from_raw(a)) {
+        Poll::Yield(Context::Intercore(&Message::Call(ref m))) => assert_eq!(m.to, 2),
+        _ => assert_eq!(1, 0),
+    }
+    from_raw(c).poll_bus(); // core 2 doesn't hear core 0
+    assert!(from_raw(c).tasks.is_empty());
+    from_raw(b).poll_bus(); // relayed by core 1
+    from_raw(c).poll_bus();
+    while !from_raw(c).results.contains_key(&0) {
+        from_raw(c).poll_tasks();
+    }
+
+    // core 0 hears core 2 directly
+    let mut msg = from_raw(a).bus.subscribers[1].recv().expect("no ack").clone();
+    from_raw(a).bus.subscribers[1].commit();
+    let ctx = intercore(from_raw(a), Some(use_(&mut msg)), &mut from_raw(a).bus);
+    match delivery(ctx, from_raw(a)) {
+        Poll::End(Context::Node(n)) => assert_eq!(format!("{}", n), "42"),
+        _ => assert_eq!(1, 0),
+    }
+}
+
+#[test]
+pub fn k_steal() {
+    // a busy core holds new spawns back until an idle one takes them
+    let mut s = cores(Topology { cores: 2, ..Topology::default() }.channels());
+    let a = into_raw(&mut s[0]);
+    let b = into_raw(&mut s[1]);
+    for &(s, peer) in &[(a, 1), (b, 0)] {
+        from_raw(s).busy = 1;
+        from_raw(s).peers = vec![peer];
+    }
+    from_raw(a).spawn(Job::Cps(CpsTask::new(from_raw(a).mem())),
+                      Termination::Recursive,
+                      Some("1")).unwrap();
+    let spawn = Spawn {
+        from: 0,
+        to: 0,
+        task_id: 0,
+        corr: 1,
+        prio: NORMAL,
+        pin: false,
+        txt: "6*7".to_string(),
+    };
+    assert!(!from_raw(a).hold(&Spawn { pin: true, ..spawn.clone() }));
+    let mut m = Message::Spawn(spawn);
+    assert_eq!(intercore(from_raw(a), Some(use_(&mut m)), &mut from_raw(a).bus), Context::Nil);
+    assert_eq!(from_raw(a).backlog.len(), 1);
+    assert_eq!(from_raw(a).load(), 2);
+
+    from_raw(b).steal();
+    from_raw(a).poll_bus();
+    assert!(from_raw(a).backlog.is_empty());
+    from_raw(b).poll_bus();
+    assert_eq!(from_raw(b).listing().len(), 1);
+
+    // the handle names the core that started it
+    let msg = from_raw(a).bus.subscribers[1].recv().expect("no ack").clone();
+    match msg {
+        Message::AckSpawn(ref x) => assert_eq!((x.from, x.to, x.corr), (1, 0, 1)),
+        x => panic!("{:?}", x),
+    }
+}
+
+fn flood(overflow: Overflow) -> (Channel, Vec<usize>) {
+    // 40 requests into a capacity-12 bus nobody reads
+    let mut c = Channel::new(0, 12);
+    c.overflow = overflow;
+    c.limit = 4;
+    c.wait = Duration::from_millis(0);
+    let s = c.publisher.subscribe();
+    c.listen(0, s);
+    for i in 0..40 {
+        bus::send(&c,
+                  Message::Names(Names {
+                      from: 0,
+                      to: 1,
+                      task_id: i,
+                      corr: 1,
+                  }));
+    }
+    let mut seen = vec![];
+    loop {
+        while let Some(m) = c.subscribers[0].recv().cloned() {
+            c.subscribers[0].commit();
+            if let Message::Names(n) = m {
+                seen.push(n.task_id);
+            }
+        }
+        if c.flush() {
+            break;
+        }
+    }
+    while let Some(m) = c.subscribers[0].recv().cloned() {
+        c.subscribers[0].commit();
+        if let Message::Names(n) = m {
+            seen.push(n.task_id);
+        }
+    }
+    (c, seen)
+}
+
+#[test]
+pub fn k_bus_overflow() {
+    // nobody reads while the flood is sent, a Block sender gives up on a full outbox at once
+    let (c, seen) = flood(Overflow::Block);
+    let room = (c.traffic.sent.get() - c.traffic.retried.get()) as usize;
+    assert_eq!(c.traffic.retried.get(), 4);
+    assert_eq!(seen, (0..room + 4).collect::<Vec<usize>>());
+    assert_eq!(c.traffic.dropped.get() as usize, 40 - room - 4);
+    assert_eq!(c.failures().iter().map(|f| f.0).collect::<Vec<usize>>(),
+               (room + 4..40).collect::<Vec<usize>>());
+
+    let (c, seen) = flood(Overflow::DropOldest);
+    let room = (c.traffic.sent.get() - c.traffic.retried.get()) as usize;
+    assert_eq!(c.traffic.retried.get(), 4);
+    assert_eq!(c.traffic.dropped.get() as usize, 40 - room - 4);
+    let mut kept: Vec<usize> = (0..room).collect();
+    kept.extend(36..40);
+    assert_eq!(seen, kept);
+
+    let (c, seen) = flood(Overflow::Error);
+    let room = c.traffic.sent.get() as usize;
+    assert_eq!(seen, (0..room).collect::<Vec<usize>>());
+    assert_eq!(c.traffic.dropped.get() as usize, 40 - room);
+    assert_eq!(c.failures().iter().map(|f| f.0).collect::<Vec<usize>>(),
+               (room..40).collect::<Vec<usize>>());
+    assert_eq!(c.queued(), 0);
+
+    // under Error a reply has no local task to fail, it waits in the outbox and is lost past the limit
+    let mut c = Channel::new(0, 4);
+    c.overflow = Overflow::Error;
+    c.limit = 2;
+    let s = c.publisher.subscribe();
+    c.listen(0, s);
+    for i in 0..8 {
+        bus::send(&c,
+                  Message::AckCall(AckCall {
+                      from: 0,
+                      to: 1,
+                      task_id: i,
+                      corr: 1,
+                      value: Value::Nil,
+                  }));
+    }
+    assert_eq!(c.queued(), 2);
+    assert_eq!(c.traffic.lost.get(), 2);
+    assert!(c.failures().is_empty());
+
+    // a Halt waits past the limit under any policy and DropOldest passes over it
+    for &(overflow, queued) in &[(Overflow::Error, 2), (Overflow::DropOldest, 3)] {
+        let mut c = Channel::new(0, 1);
+        c.overflow = overflow;
+        c.limit = 1;
+        let s = c.publisher.subscribe();
+        c.listen(0, s);
+        for i in 0..3 {
+            if i == 1 {
+                for to in 1..3 {
+                    bus::force(&c, Message::Halt(Halt { from: 0, to: to, code: 3 }));
+                }
+            }
+            bus::send(&c,
+                      Message::AckCall(AckCall {
+                          from: 0,
+                          to: 1,
+                          task_id: i,
+                          corr: 1,
+                          value: Value::Nil,
+                      }));
+        }
+        assert_eq!(c.queued(), queued);
+        let mut halts = 0;
+        loop {
+            while let Some(m) = c.subscribers[0].recv().cloned() {
+                c.subscribers[0].commit();
+                if let Message::Halt(_) = m {
+                    halts += 1;
+                }
+            }
+            if c.flush() {
+                break;
+            }
+        }
+        assert_eq!(halts, 2);
+    }
+}
+
+#[test]
+pub fn k_request_timeout() {
+    // nobody serves core 1, the request expires and its answer comes too late
+    let ref mut sched = Scheduler::with_channel(0);
+    sched.pending = Pending::new(Duration::from_millis(0));
+    let s = into_raw(sched);
+    let code = "p:pub[1;8]";
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+    let t = into_raw(sched.task(shell.0).expect("no shell"));
+    match from_raw(t).0.poll(Context::Nil, from_raw(s)) {
+        Poll::Yield(Context::Intercore(&Message::Pub(ref m))) => assert_eq!(m.corr, 1),
+        _ => assert_eq!(1, 0),
+    }
+    assert_eq!(from_raw(s).pending.len(), 1);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(from_raw(s).pending.expired(), vec![(shell.0, 1)]);
+    match delivery(Context::Fail(shell.0, "request 1 timed out".to_string()), from_raw(s)) {
+        Poll::Err(_) => (),
+        _ => assert_eq!(1, 0),
+    }
+
+    let mut ack = Message::AckPub(AckPub {
+        from: 1,
+        to: 0,
+        task_id: shell.0,
+        corr: 1,
+        result_id: 0,
+    });
+    assert_eq!(intercore(from_raw(s), Some(use_(&mut ack)), &mut from_raw(s).bus),
+               Context::Nil);
+}
+
+#[test]
+pub fn k_wire_message() {
+    let m = Message::AckCall(AckCall {
+        from: 1,
+        to: 0,
+        task_id: 3,
+        corr: 7,
+        value: Value::List(vec![Value::Number(-5), Value::VecFloat(vec![2.5, 0.1]), Value::Bool(true)]),
+    });
+    assert_eq!(Message::from_bytes(&m.to_bytes()), Some(m));
+    let s = Message::Spawn(Spawn {
+        from: 0,
+        to: 2,
+        task_id: 0,
+        corr: 2,
+        prio: 6,
+        pin: true,
+        txt: "a:1".to_string(),
+    });
+    assert_eq!(Message::from_bytes(&s.to_bytes()), Some(s));
+    assert_eq!(Message::from_bytes(&[4, 0, 0]), None);
+
+    // the cursor stays behind, the rest of an AckSub comes through
+    let p: Publisher<Value> = Publisher::with_capacity(4);
+    let a = AckSub {
+        from: 1,
+        to: 0,
+        task_id: 2,
+        corr: 5,
+        result_id: 0,
+        ty: bus::TypeId::Float,
+        s: Some(p.subscribe()),
+    };
+    let bytes = Message::AckSub(a.clone()).to_bytes();
+    assert_eq!(Message::from_bytes(&bytes), Some(Message::AckSub(AckSub { s: None, ..a })));
+
+    // a count longer than the frame fails before anything is allocated
+    assert_eq!(wire::read_value(&mut Reader::new(&[14, 255, 255, 255, 255]), None), None);
+}
+
+#[test]
+pub fn k_wire_ast() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+    let mut mem2 = Memory::new();
+    let h2 = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem2 as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"(`abc;{[x;y]x+y*2};1 2 3)".to_string());
+    let mut out = vec![];
+    wire::ast(code, &h.borrow_mut().arena, &mut out);
+    let back = wire::read_ast(&mut Reader::new(&out), &h2.borrow_mut().arena).unwrap();
+    assert_eq!(source::ast(back, &h2.borrow_mut().arena), "[(`abc;{[x;y]x+y*2};(1;2;3))]");
+
+    let code = h.borrow_mut().parse(&"`b;`def".to_string());
+    let v = h.borrow_mut().run(code, Context::Nil, None).unwrap();
+    let mut out = vec![];
+    wire::ast(v, &h.borrow_mut().arena, &mut out);
+    match wire::read_ast(&mut Reader::new(&out), &h2.borrow_mut().arena) {
+        Some(&AST::Atom(Atom::Value(Value::SymbolInt(s)))) => {
+            assert_eq!(h2.borrow_mut().arena.symbol_name(s), Some("def".to_string()))
+        }
+        _ => assert_eq!(1, 0),
+    }
+
+    // unparsable source is a decode error
+    let mut out = vec![2];
+    wire::put_str(&mut out, ")](");
+    assert!(wire::read_ast(&mut Reader::new(&out), &h2.borrow_mut().arena).is_none());
+}
+
+fn run_local(code: &'static str) -> String {
+    // drives a shell on a single core, answering its intercore requests in place
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+    drive(s, shell.0)
+}
+
+fn run_local_err(code: &'static str) {
+    // like run_local, for code that has to fail
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+    drive_err(s, shell.0)
+}
+
+fn drive<'a>(s: *mut Scheduler<'a>, task: usize) -> String {
+    let t = into_raw(from_raw(s).task(task).expect("no shell"));
+    let mut ctx = Context::Nil;
+    loop {
+        match from_raw(t).0.poll(ctx.clone(), from_raw(s)) {
+            Poll::Yield(Context::Intercore(m)) => {
+                let mut msg = m.clone();
+                ctx = intercore(from_raw(s), Some(use_(&mut msg)), &mut from_raw(s).bus);
+            }
+            Poll::End(Context::Node(n)) => return format!("{}", n),
+            x => panic!("unexpected poll {:?}", x),
+        }
+    }
+}
+
+fn drive_err<'a>(s: *mut Scheduler<'a>, task: usize) {
+    // like drive, for a task that has to fail
+    let t = into_raw(from_raw(s).task(task).expect("no shell"));
+    let mut ctx = Context::Nil;
+    loop {
+        match from_raw(t).0.poll(ctx.clone(), from_raw(s)) {
+            Poll::Yield(Context::Intercore(m)) => {
+                let mut msg = m.clone();
+                ctx = intercore(from_raw(s), Some(use_(&mut msg)), &mut from_raw(s).bus);
+            }
+            Poll::Err(_) => return,
+            x => panic!("unexpected poll {:?}", x),
+        }
+    }
+}
+
+#[test]
+pub fn k_priorities() {
+    // runnable tasks are polled by priority, equal ones in spawn order
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    for code in &["1", "2", "3", "4"] {
+        from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                          Termination::Recursive,
+                          Some(code)).unwrap();
+    }
+    assert_eq!(from_raw(s).prioritize(2, 7), Some(NORMAL));
+    assert_eq!(from_raw(s).prioritize(9, 7), None);
+    let mut m = Message::QoS(QoS {
+        from: 0,
+        to: 0,
+        task_id: 3,
+        corr: 1,
+        pid: 0,
+        prio: 1,
+    });
+    from_raw(s).pending.expect(&m);
+    assert_eq!(intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus),
+               Context::NodeAck(3, NORMAL as usize));
+
+    let order: Vec<usize> = (0..4).filter_map(|_| from_raw(s).runq.pop()).collect();
+    assert_eq!(order, vec![2, 1, 3, 0]);
+    for &i in &order {
+        let p = from_raw(s).task(i).expect("no task").2;
+        from_raw(s).runq.push(i, p);
+    }
+    assert_eq!(from_raw(s).poll_tasks(), 4);
+    assert_eq!(from_raw(s).results.get(&2), Some(&Value::Number(3)));
+    assert_eq!(from_raw(s).poll_tasks(), 0); // nothing is runnable any more
+    assert_eq!(from_raw(s).task(0).expect("no task").0.state(), State::Sleeping);
+
+    // a malformed qos fails the caller
+    run_local_err("qos[1;2]");
+    run_local_err("qos[(0;0);300]");
+}
+
+#[test]
+pub fn k_task_lifecycle() {
+    // ended tasks are reaped, their value stays until the slot comes back with a new pid
+    let mut c = Channel::new(0, 16);
+    let sub = c.publisher.subscribe();
+    c.listen(0, sub);
+    let ref mut sched = Scheduler::with_channel2(c);
+    sched.max_tasks = 3;
+    let s = into_raw(sched);
+    let a = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                              Termination::Recursive,
+                              Some("6*7")).unwrap();
+    let b = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                              Termination::Recursive,
+                              Some("1")).unwrap();
+    assert_eq!(from_raw(s).listing(),
+               vec![(0, "runnable".to_string(), NORMAL), (1, "runnable".to_string(), NORMAL)]);
+
+    let mut m = Message::Kill(Kill {
+        from: 0,
+        to: 0,
+        task_id: a.0,
+        corr: 1,
+        pid: b.0,
+    });
+    from_raw(s).pending.expect(&m);
+    assert_eq!(intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus),
+               Context::NodeAck(a.0, 1));
+    from_raw(s).pending.expect(&m);
+    assert_eq!(intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus),
+               Context::Fail(a.0, "1: no such task".to_string()));
+
+    assert_eq!(from_raw(s).poll_tasks(), 1);
+    assert_eq!(from_raw(s).results.get(&a.0), Some(&Value::Number(42)));
+    assert_eq!(from_raw(s).results.get(&b.0), Some(&Value::Nil));
+    assert_eq!(from_raw(s).slot(a.0), None);
+    assert!(from_raw(s).tasks[a.0].is_none() && from_raw(s).tasks[b.0].is_none());
+    assert!(from_raw(s).listing().is_empty());
+
+    let mut m = Message::Tasks(Tasks {
+        from: 0,
+        to: 0,
+        task_id: 0,
+        corr: 2,
+    });
+    intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus);
+    while let Some(m) = from_raw(s).bus.subscribers[0].recv().cloned() {
+        from_raw(s).bus.subscribers[0].commit();
+        if let Message::AckTasks(t) = m {
+            assert!(t.tasks.is_empty());
+        }
+    }
+
+    from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                      Termination::Recursive,
+                      Some("1")).unwrap();
+    assert!(!from_raw(s).full());
+    let c = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                              Termination::Recursive,
+                              Some("2")).unwrap();
+    assert_eq!(c.0, TASKS_MAX_CNT + b.0); // b was reaped first
+    assert_eq!(from_raw(s).results.get(&b.0), None);
+    assert_eq!(from_raw(s).results.get(&a.0), Some(&Value::Number(42)));
+    assert_eq!(from_raw(s).slot(c.0), Some(b.0));
+    assert_eq!(from_raw(s).slot(b.0), None);
+
+    // with every slot taken spawn gives nothing back
+    from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                      Termination::Recursive,
+                      Some("3")).unwrap();
+    assert!(from_raw(s).full());
+    assert!(from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                              Termination::Recursive,
+                              Some("4"))
+        .is_none());
+}
+
+#[test]
+pub fn k_stats() {
+    // ring cursors count what went through and what found the ring full or empty
+    let p: Publisher<u64> = Publisher::with_capacity(2);
+    let sub = p.subscribe();
+    for x in 0..3 {
+        if let Some(v) = p.next() {
+            *v = x;
+            p.commit();
+        }
+    }
+    assert!(sub.recv_n(2).is_some());
+    sub.commit();
+    assert!(sub.recv().is_none());
+    assert!(!sub.ready(1)); // a peek is not a failed receive
+    let c = p.counters();
+    assert_eq!((c.succ_items, c.succ_opers, c.fail_opers), (2, 2, 1));
+    let c = sub.counters();
+    assert_eq!((c.succ_items, c.succ_opers, c.fail_opers), (2, 1, 1));
+
+    let mut c = Channel::new(0, 16);
+    let sub = c.publisher.subscribe();
+    c.listen(0, sub);
+    let ref mut sched = Scheduler::with_channel2(c);
+    let s = into_raw(sched);
+    from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                      Termination::Recursive,
+                      Some("1+2")).unwrap();
+    from_raw(s).poll_tasks();
+    let mut m = Message::Metrics(Metrics {
+        from: 0,
+        to: 0,
+        task_id: 0,
+        corr: 1,
+    });
+    intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus);
+    let mut rows = vec![];
+    while let Some(m) = from_raw(s).bus.subscribers[0].recv().cloned() {
+        from_raw(s).bus.subscribers[0].commit();
+        if let Message::AckMetrics(a) = m {
+            rows = a.stats;
+        }
+    }
+    let get = |k: &str| rows.iter().find(|r| r.0 == k).map(|r| r.1);
+    assert_eq!(get("tasks"), Some(0));
+    assert_eq!(get("spawned"), Some(1));
+    assert_eq!(get("steps"), Some(1));
+    assert_eq!(get("sent"), Some(1));
+    assert_eq!(stats::json(2, &rows[..2]), "{\"core\":2,\"tasks\":0,\"spawned\":1}");
+}
+
+#[test]
+pub fn k_halt() {
+    // exit[] stops this core and tells the peers once, with the code
+    let h = Message::Halt(Halt { from: 2, to: 0, code: 130 });
+    assert_eq!(Message::from_bytes(&h.to_bytes()), Some(h));
+
+    let mut c = Channel::new(0, 16);
+    let sub = c.publisher.subscribe();
+    let seen = c.publisher.subscribe();
+    c.listen(0, sub);
+    let ref mut sched = Scheduler::with_channel2(c);
+    sched.peers = vec![1, 2];
+    let s = into_raw(sched);
+    from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                      Termination::Recursive,
+                      Some("exit[3]")).unwrap();
+    from_raw(s).poll_tasks();
+    while from_raw(s).poll_bus() > 0 {}
+    assert_eq!(from_raw(s).halting, Some(3));
+    let mut told = vec![];
+    while let Some(m) = seen.recv().cloned() {
+        seen.commit();
+        if let Message::Halt(h) = m {
+            told.push((h.to, h.code));
+        }
+    }
+    assert_eq!(told, vec![(0, 3), (1, 3), (2, 3)]);
+    from_raw(s).halt(9); // already halting
+    assert_eq!(from_raw(s).shutdown(), 3);
+
+    // a bad code fails the task, the core goes on
+    run_local_err("exit[256]");
+    run_local_err("exit `a");
+}
+
+#[test]
+pub fn k_signals() {
+    // signals go to .z.sig in the shell once it is idle, without it SIGINT and SIGTERM halt and SIGHUP does nothing
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some("a:1")).unwrap();
+    drive(s, shell.0);
+    from_raw(s).signal(shell, 1);
+    assert_eq!(from_raw(s).halting, None);
+    from_raw(s).exec(shell, Some(".z.sig:{[n] a:n}; p:pub[0;8]; s:sub[0;p]; rcv s"));
+    let t = into_raw(from_raw(s).task(shell.0).expect("no shell"));
+    let mut ctx = Context::Nil;
+    loop {
+        match from_raw(t).0.poll(ctx.clone(), from_raw(s)) {
+            Poll::Yield(Context::Intercore(m)) => {
+                let mut msg = m.clone();
+                ctx = intercore(from_raw(s), Some(use_(&mut msg)), &mut from_raw(s).bus);
+            }
+            Poll::Yield(Context::Nil) => break,
+            x => panic!("unexpected poll {:?}", x),
+        }
+    }
+    from_raw(s).signal(shell, 15); // the shell waits in rcv, the signal waits for it
+    assert_eq!(from_raw(s).signals.len(), 1);
+    let p = &from_raw(s).queues.publishers()[0];
+    if let Some(slot) = p.next() {
+        *slot = Value::Number(7);
+        p.commit();
+    }
+    match from_raw(t).0.poll(Context::Nil, from_raw(s)) {
+        Poll::End(Context::Node(n)) => assert_eq!(format!("{}", n), "7"),
+        x => panic!("unexpected poll {:?}", x),
+    }
+    assert_eq!(from_raw(s).poll_signals(shell), 1);
+    assert_eq!(from_raw(s).halting, None);
+    from_raw(s).exec(shell, Some("a"));
+    assert_eq!(drive(s, shell.0), "15");
+
+    let ref mut sched = Scheduler::with_channel(1);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some("a:1")).unwrap();
+    drive(s, shell.0);
+    from_raw(s).signal(shell, 2);
+    assert_eq!(from_raw(s).halting, Some(130));
+}
+
+#[test]
+pub fn k_supervisor() {
+    // crashed children restart together until the intensity runs out, links crash the linked
+    let mut c = Channel::new(0, 64);
+    let sub = c.publisher.subscribe();
+    c.listen(0, sub);
+    let ref mut sched = Scheduler::with_channel2(c);
+    let s = into_raw(sched);
+    let owner = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  None).unwrap();
+    let sup = from_raw(s).supervise(owner.0, Strategy::OneForAll, 1, Duration::from_secs(60));
+    let code = "p:pub[0;8]; s:sub[0;p]; rcv s".to_string();
+    assert_eq!(from_raw(s).child(sup, 0, NORMAL, code.clone()), Some(0));
+    assert_eq!(from_raw(s).child(sup, 0, NORMAL, code), Some(1));
+    while from_raw(s).poll_bus() > 0 {}
+    let pids: Vec<usize> = from_raw(s).sups[&sup].children.iter().filter_map(|c| c.pid).collect();
+    assert_eq!(pids.len(), 2);
+    let (a, b) = (pids[0], pids[1]);
+
+    let l = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                              Termination::Recursive,
+                              Some("rcv 0")).unwrap();
+    let mut m = Message::Watch(Watch {
+        from: 0,
+        to: 0,
+        task_id: l.0,
+        corr: 1,
+        pid: a,
+        by: Watcher::Link(0, l.0),
+    });
+    from_raw(s).pending.expect(&m);
+    assert_eq!(intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus),
+               Context::NodeAck(l.0, 1));
+
+    assert!(from_raw(s).kill(a));
+    while from_raw(s).poll_bus() > 0 {}
+    let restarted: Vec<usize> = from_raw(s).sups[&sup].children.iter().filter_map(|c| c.pid).collect();
+    assert_eq!(restarted.len(), 2);
+    assert!(!restarted.contains(&a) && !restarted.contains(&b));
+    assert_eq!(from_raw(s).slot(b), None); // stopped by one-for-all
+    assert_eq!(from_raw(s).slot(l.0), None); // died with a
+
+    assert!(from_raw(s).kill(restarted[1]));
+    while from_raw(s).poll_bus() > 0 {}
+    assert!(from_raw(s).sups.is_empty()); // gave up, its children stop
+    assert_eq!(from_raw(s).slot(restarted[0]), None);
+}
+
+#[test]
+pub fn k_watch_failures() {
+    // bad arguments fail the caller, links go both ways, monitors wait for room and
+    // a child its core has no slot for counts as crashed
+    let mut c = Channel::new(0, 64);
+    let sub = c.publisher.subscribe();
+    c.listen(0, sub);
+    let ref mut sched = Scheduler::with_channel2(c);
+    sched.max_tasks = 4;
+    let s = into_raw(sched);
+    for code in &["sup[`foo;1;100]", "sup[1;2]", "monitor[1]"] {
+        let t = from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+        drive_err(s, t.0);
+        from_raw(s).finish(t.0, Value::Nil, true);
+    }
+
+    let x = from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                              Termination::Recursive,
+                              Some("rcv 0")).unwrap();
+    let y = from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                              Termination::Recursive,
+                              Some("rcv 0")).unwrap();
+    let mut m = Message::Watch(Watch {
+        from: 0,
+        to: 0,
+        task_id: x.0,
+        corr: 1,
+        pid: y.0,
+        by: Watcher::Link(0, x.0),
+    });
+    from_raw(s).pending.expect(&m);
+    assert_eq!(intercore(from_raw(s), Some(use_(&mut m)), &mut from_raw(s).bus),
+               Context::NodeAck(x.0, 1));
+    assert!(from_raw(s).kill(x.0)); // the caller crashes, the task it linked to goes too
+    while from_raw(s).poll_bus() > 0 {}
+    assert_eq!(from_raw(s).slot(y.0), None);
+
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                                  Termination::Corecursive,
+                                  Some("p:pub[0;2;`v]; s:sub[0;p]; snd[p;0 0 0]; snd[p;0 0 0]; p")).unwrap();
+    let p: usize = drive(s, shell.0).parse().unwrap();
+    let d = Down {
+        from: 0,
+        to: 0,
+        pid: 7,
+        crashed: true,
+        by: Watcher::Monitor(0, p),
+    };
+    from_raw(s).down(&d);
+    assert_eq!(from_raw(s).downs.len(), 1);
+    from_raw(s).exec(shell, Some("rcvall s"));
+    drive(s, shell.0);
+    assert_eq!(from_raw(s).poll_downs(), 1);
+    from_raw(s).exec(shell, Some("rcv s"));
+    assert_eq!(drive(s, shell.0), "#i[0;7;1]");
+
+    let owner = from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                                  Termination::Recursive,
+                                  Some("rcv 0")).unwrap();
+    let mut fillers = vec![];
+    while !from_raw(s).full() {
+        fillers.push(from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                                       Termination::Recursive,
+                                       Some("rcv 0")).unwrap());
+    }
+    let sup = from_raw(s).supervise(owner.0, Strategy::OneForOne, 1, Duration::from_secs(60));
+    assert_eq!(from_raw(s).child(sup, 0, NORMAL, "1".to_string()), Some(0));
+    while from_raw(s).poll_bus() > 0 {}
+    assert!(from_raw(s).sups.is_empty()); // the restart found no slot either, it gave up
+    assert_eq!(from_raw(s).slot(owner.0), None);
+    assert!(fillers.iter().all(|f| from_raw(s).slot(f.0).is_some()));
+}
+
+#[test]
+pub fn k_timers() {
+    // timers run in the shell that set them, once it is idle
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let code = "p:pub[0;8]; s:sub[0;p]; after[0;{snd[p;42]}]; u:every[10;{snd[p;7]}]; cancel u";
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+    assert_eq!(drive(s, shell.0), "1");
+    assert_eq!(from_raw(s).alarms.len(), 1);
+    thread::sleep(Duration::from_millis(30));
+    from_raw(s).poll_timers();
+    assert_eq!(from_raw(s).alarms.len(), 0);
+    from_raw(s).exec(shell, Some("rcvall s"));
+    assert_eq!(drive(s, shell.0), "#i[42]");
+
+    // every[ms] alone calls .z.ts, every 0 stops it
+    from_raw(s).exec(shell, Some(".z.ts:{snd[p;5]}; every 20"));
+    drive(s, shell.0);
+    for _ in 0..2 {
+        thread::sleep(Duration::from_millis(50));
+        from_raw(s).poll_timers();
+    }
+    from_raw(s).exec(shell, Some("every 0; cancel 99; rcvall s"));
+    assert_eq!(drive(s, shell.0), "#i[5;5]");
+    assert_eq!(from_raw(s).ts, None);
+
+    // a timer in the past, code run every 0 ms or bad arguments fail the task that asks for it
+    for code in &["after[-1;{1}]", "every[0;{1}]", "after[1;2]", "every `a", "cancel `a"] {
+        let t = from_raw(s).spawn(Job::Cps(CpsTask::new(from_raw(s).mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+        drive_err(s, t.0);
+    }
+    assert_eq!(from_raw(s).alarms.len(), 0);
+}
+
+#[test]
+pub fn k_typed_queues() {
+    assert_eq!(run_local("p:pub[0;8;`a]; s:sub[0;p]; snd[p;(`ibm;101.5;300)]; rcv s"),
+               "#a[1;101.5;300]");
+    assert_eq!(run_local("p:pub[0;8;`f]; s:sub[0;p]; snd[p;2.5]; rcv s"), "2.5");
+    assert_eq!(run_local("p:pub[0;8;`v]; s:sub[0;p]; snd[p;1 2 3]; rcv s"), "#i[1;2;3]");
+    assert_eq!(run_local("p:pub[0;8;`s]; s:sub[0;p]; snd[p;`msft]; rcv s"), "1");
+}
+
+#[test]
+pub fn k_typed_queue_mismatch() {
+    // the task fails, the core and its queue go on
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some("p:pub[0;8;`f]; snd[p;1]")).unwrap();
+    drive_err(s, shell.0);
+    assert_eq!(from_raw(s).queues.publishers()[0].counters().succ_items, 0);
+}
+
+#[test]
+pub fn k_rcv_timeout() {
+    assert_eq!(run_local("p:pub[0;8]; s:sub[0;p]; rcv[s;0]"), "Nil");
+    assert_eq!(run_local("p:pub[0;8]; s:sub[0;p]; trcv s"), "Nil");
+    assert_eq!(run_local("p:pub[0;8]; s:sub[0;p]; snd[p;5]; trcv s"), "5");
+    run_local_err("p:pub[0;8]; s:sub[0;p]; rcv[s;-1]");
+    run_local_err("p:pub[0;8]; s:sub[0;p]; rcvn[s;2;-1]");
+    run_local_err("trcv 99");
+    run_local_err("trcv `a");
+}
+
+#[test]
+pub fn k_batch_queues() {
+    assert_eq!(run_local("p:pub[0;8]; s:sub[0;p]; snd[p;1 2 3 4]; rcvn[s;3]"), "#i[1;2;3]");
+    assert_eq!(run_local("p:pub[0;8]; s:sub[0;p]; snd[p;1 2 3]; snd[p;4]; rcv s; rcvall s"), "#i[2;3;4]");
+    assert_eq!(run_local("p:pub[0;8;`f]; s:sub[0;p]; rcvall s"), "#f[]");
+    assert_eq!(run_local("p:pub[0;8;`v]; s:sub[0;p]; snd[p;1 2]; rcv s"), "#i[1;2]");
+    assert_eq!(run_local("p:pub[0;8]; s:sub[0;p]; snd[p;1 2]; rcvn[s;3;0]"), "Nil");
+    // batches that run past the end of the ring come back in order
+    assert_eq!(run_local("p:pub[0;4]; s:sub[0;p]; snd[p;1 2 3]; rcvall s; snd[p;4 5 6]; rcvn[s;3]"),
+               "#i[4;5;6]");
+    assert_eq!(run_local("p:pub[0;4]; s:sub[0;p]; snd[p;1 2 3]; rcvn[s;2]; snd[p;4 5 6]; rcvall s"),
+               "#i[3;4;5;6]");
+    // a batch the ring can't hold fails at once, as does an unknown subscriber
+    run_local_err("p:pub[0;8]; s:sub[0;p]; rcvn[s;9]");
+    run_local_err("p:pub[0;8]; s:sub[0;p]; rcvn[s;-1]");
+    run_local_err("p:pub[0;8]; s:sub[0;p]; rcvn[s;0]");
+    run_local_err("rcvall 99");
+}
+
+#[test]
+pub fn k_batch_queue_full() {
+    // a batch that doesn't fit fails the task and writes nothing
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some("p:pub[0;4]; s:sub[0;p]; snd[p;1 2 3]; snd[p;4 5]")).unwrap();
+    drive_err(s, shell.0);
+    assert_eq!(from_raw(s).queues.publishers()[0].counters().succ_items, 3);
+
+    // more than the ring holds never fits, even with nobody subscribed
+    run_local_err("p:pub[0;4]; snd[p;1 2 3 4 5 6 7 8 9]");
+    let p: Publisher<u64> = Publisher::with_capacity(4);
+    assert!(p.next_n_split(5).is_none());
+    assert_eq!(p.counters().fail_opers, 1);
+}
+
+#[test]
+pub fn k_named_queues() {
+    assert_eq!(run_local("p:pub[0;8;`;`trades]; s:sub[`trades]; snd[p;7]; rcv s"), "7");
+    assert_eq!(run_local("p:pub[0;8;`f;`px]; s:sub[`px]; snd[p;1.5]; rcv s"), "1.5");
+}
+
+#[test]
+pub fn k_named_queue_conflict() {
+    // the registrar refuses a taken name before a queue is made for it
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some("p:pub[0;8;`;`trades]; q:pub[0;4;`;`trades]")).unwrap();
+    drive_err(s, shell.0);
+    assert_eq!(from_raw(s).queues.publishers().len(), 1);
+    assert_eq!(from_raw(s).registry["trades"].pub_id, 0);
+}
+
+#[test]
+pub fn k_named_queue_missing() {
+    // a name whose queue is gone is refused at once rather than left to time out
+    let ref mut sched = Scheduler::with_channel(0);
+    sched.registry.insert("ghost".to_string(),
+                          Registered {
+                              core: 0,
+                              pub_id: 5,
+                              owner: 0,
+                              task_id: 0,
+                          });
+    let s = into_raw(sched);
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some("s:sub[`ghost]")).unwrap();
+    drive_err(s, shell.0);
+}
+
+#[test]
+pub fn k_registry() {
+    let mut s = cores(pair(true));
+    let a = into_raw(&mut s[0]);
+    let b = into_raw(&mut s[1]);
+    let code = "p:pub[1;8;`;`trades]; s:sub[`trades]; snd[p;5]; x:rcv s; pubs[]";
+    let shell = from_raw(b).spawn(Job::Cps(CpsTask::new(from_raw(b).mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+
+    let t = into_raw(from_raw(b).task(shell.0).expect("no shell"));
+    match from_raw(t).0.poll(Context::Nil, from_raw(b)) {
+        Poll::Yield(Context::Intercore(&Message::Pub(ref m))) => assert_eq!(m.name, "trades"),
+        _ => assert_eq!(1, 0),
+    }
+    // core 0 keeps the registry, the shell on core 1 is driven by hand to catch its result
+    let mut out = String::new();
+    for _ in 0..12 {
+        from_raw(a).poll_bus();
+        for k in 0..2 {
+            let m = from_raw(b).bus.subscribers[k].recv().cloned();
+            from_raw(b).bus.subscribers[k].commit();
+            if let Some(mut msg) = m {
+                if let Message::AckNames(ref n) = msg {
+                    assert_eq!(n.names, vec![("trades".to_string(), 1, 0)]);
+                }
+                let ctx = intercore(from_raw(b), Some(use_(&mut msg)), &mut from_raw(b).bus);
+                if let Poll::End(Context::Node(n)) = delivery(ctx, from_raw(b)) {
+                    out = format!("{}", n);
+                }
+            }
+        }
+    }
+    assert_eq!(from_raw(a).registry["trades"].core, 1);
+    assert_eq!(out, "#a[#a[0;1;0]]");
+}
+
+#[test]
+pub fn k_blocking_rcv() {
+    let ref mut sched = Scheduler::with_channel(0);
+    let s = into_raw(sched);
+    let code = "p:pub[0;8]; s:sub[0;p]; x:rcv s; x+1";
+    let shell = from_raw(s).spawn(Job::Cps(CpsTask::new(sched.mem())),
+                                  Termination::Corecursive,
+                                  Some(code)).unwrap();
+    let t = into_raw(sched.task(shell.0).expect("no shell"));
+    let mut ctx = Context::Nil;
+    for _ in 0..2 {
+        match from_raw(t).0.poll(ctx.clone(), from_raw(s)) {
+            Poll::Yield(Context::Intercore(m)) => {
+                let mut msg = m.clone();
+                ctx = intercore(from_raw(s), Some(use_(&mut msg)), &mut from_raw(s).bus);
+            }
+            _ => assert_eq!(1, 0),
+        }
+    }
+    // the queue is empty: rcv suspends the shell, polling it again leaves it asleep
+    match from_raw(t).0.poll(ctx.clone(), from_raw(s)) {
+        Poll::Yield(Context::Nil) => (),
+        _ => assert_eq!(1, 0),
+    }
+    assert!(from_raw(t).0.blocked());
+    match from_raw(t).0.poll(Context::Nil, from_raw(s)) {
+        Poll::Yield(Context::Nil) => (),
+        _ => assert_eq!(1, 0),
+    }
+
+    let p = &from_raw(s).queues.publishers()[0];
+    if let Some(slot) = p.next() {
+        *slot = Value::Number(41);
+        p.commit();
+    }
+    match from_raw(t).0.poll(Context::Nil, from_raw(s)) {
+        Poll::End(Context::Node(n)) => assert_eq!(format!("{}", n), "42"),
+        _ => assert_eq!(1, 0),
+    }
+    assert!(!from_raw(t).0.blocked());
+}
 
 #[test]
 pub fn k_partial1() {
@@ -622,3 +1806,178 @@ pub fn k_vecop_vv() {
     assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
                "#i[0;0;0]");
 }
+
+#[test]
+pub fn k_promote() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"1+2.5".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "3.5");
+    let code = h.borrow_mut().parse(&"(1;2)*0.5".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#f[0.5;1]");
+    let code = h.borrow_mut().parse(&"(1.5;2.5)>(1;3)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#i[1;0]");
+}
+
+#[test]
+pub fn k_divide() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"7%2".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "3.5");
+    let code = h.borrow_mut().parse(&"(4;6)%2".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#f[2;3]");
+}
+
+#[test]
+pub fn k_nulls() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"(1;0N;3)+1".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#i[2;0N;4]");
+    let code = h.borrow_mut().parse(&"0N*2.5".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "0n");
+    let code = h.borrow_mut().parse(&"9223372036854775807+1".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "0N");
+}
+
+#[test]
+pub fn k_widths() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"0101b;0x0a0b;(1h;2h);3i;2.5e".to_string());
+    assert_eq!(code,
+               &AST::Vector(vec![av(Value::VecBool(vec![false, true, false, true])),
+                                 av(Value::VecByte(vec![10, 11])),
+                                 AST::Atom(Atom::List(&av(Value::VecShort(vec![1, 2])))),
+                                 av(Value::Int32(3)),
+                                 av(Value::Real(2.5))]));
+}
+
+#[test]
+pub fn k_widths_promote() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"0101b+1b".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#i32[1;2;1;2]");
+    let code = h.borrow_mut().parse(&"2h*3".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "6");
+    let code = h.borrow_mut().parse(&"2.5e+1i".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "3.5e");
+}
+
+#[test]
+pub fn k_cast() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"`bool$(0;2;0)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "010b");
+    let code = h.borrow_mut().parse(&"`j$3.7".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "4");
+    let code = h.borrow_mut().parse(&"`h$(2.5;1.2)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#h[3;1]");
+    let code = h.borrow_mut().parse(&"`float$0N".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "0n");
+    assert!(command::parse_Mex(&h.borrow_mut().arena, "70000h").is_err());
+    assert!(command::parse_Mex(&h.borrow_mut().arena, "3000000000i").is_err());
+    assert!(command::parse_Mex(&h.borrow_mut().arena, "99999999999999999999j").is_err());
+}
+
+#[test]
+pub fn k_general_list() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"1+(1;2 3;4)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#a[2;#i[3;4];5]");
+    let code = h.borrow_mut().parse(&"(1;2.5)*(2;(1;2))".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#a[2;#f[2.5;5]]");
+    let code = h.borrow_mut().parse(&"a:10;(a;2)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#i[10;2]");
+    let code = h.borrow_mut().parse(&"1 2 3+1".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#i[2;3;4]");
+}
+
+#[test]
+pub fn k_projection() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"q:+[1;];q 2".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "3");
+    let code = h.borrow_mut().parse(&"p:2*;p 3".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "6");
+    let code = h.borrow_mut().parse(&"f:{x+1};g:{x*2};fg:f g;fg 3".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "7");
+}
+
+#[test]
+pub fn k_each_over() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"{x*2}'(1;2;3)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#i[2;4;6]");
+    let code = h.borrow_mut().parse(&"+/(1;2;3)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "6");
+}
+
+#[test]
+pub fn k_builtin_projection() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+    h.borrow_mut().define_primitives();
+
+    let code = h.borrow_mut().parse(&"pr:print[;2];pr'(1;3)".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "#a[#a[1;2];#a[3;2]]");
+}
+
+#[test]
+pub fn k_implicit_args() {
+    let mut mem = Memory::new();
+    let h = handle::new(Interpreter::new(unsafe { UnsafeShared::new(&mut mem as *mut Memory) }).unwrap());
+
+    let code = h.borrow_mut().parse(&"{x+y}[1;2]".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "3");
+    let code = h.borrow_mut().parse(&"{x+y*z}[1;2;3]".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "7");
+    let code = h.borrow_mut().parse(&"p:{x-y}[10];p 4".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "6");
+    let code = h.borrow_mut().parse(&"p:{z}[;;3];p[1;2]".to_string());
+    assert_eq!(format!("{}", h.borrow_mut().run(code, Context::Nil, None).unwrap()),
+               "3");
+}